@@ -1,13 +1,18 @@
 use aoc::read_file_input;
-use std::{cmp::Ordering, str::FromStr};
+use itertools::Itertools;
+use std::{cmp::Ordering, env, str::FromStr};
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 enum Move {
     Rock = 1,
     Paper = 2,
     Scissors = 3,
 }
 
+impl Move {
+    const ALL: [Move; 3] = [Move::Rock, Move::Paper, Move::Scissors];
+}
+
 impl PartialOrd for Move {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         if self == &Move::Scissors && other == &Move::Rock {
@@ -33,11 +38,120 @@ impl FromStr for Move {
     }
 }
 
+#[derive(Debug)]
+struct StrategyAnalysis {
+    best_score: u32,
+    worst_score: u32,
+    random_expected_score: f64,
+    random_score_variance: f64,
+    best_mapping: [Move; 3],
+    best_mapping_score: u32,
+}
+
 fn main() {
     let input = read_file_input("02.txt".to_string());
 
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
+
+    if env::args().any(|arg| arg == "--analyze") {
+        let analysis = analyze(&input);
+
+        println!();
+        println!("  Best score: {}", analysis.best_score);
+        println!("  Worst score: {}", analysis.worst_score);
+        println!(
+            "  Random score: {:.2} (variance {:.2})",
+            analysis.random_expected_score, analysis.random_score_variance
+        );
+        println!(
+            "  Best mapping: X = {:?}, Y = {:?}, Z = {:?} ({})",
+            analysis.best_mapping[0],
+            analysis.best_mapping[1],
+            analysis.best_mapping[2],
+            analysis.best_mapping_score
+        );
+    }
+}
+
+fn round_score(them: Move, us: Move) -> u32 {
+    match them.partial_cmp(&us) {
+        // Draw.
+        Some(Ordering::Equal) => 3 + us as u32,
+        // We win.
+        Some(Ordering::Less) => 6 + us as u32,
+        // They win.
+        Some(Ordering::Greater) => us as u32,
+        None => {
+            panic!("Moves should be comparable")
+        }
+    }
+}
+
+// Opponent move paired with the index (0, 1, 2) of the X/Y/Z response column.
+fn guide(input: &str) -> Vec<(Move, usize)> {
+    input
+        .lines()
+        .map(|line| {
+            let (them, response) = line.split_once(' ').unwrap();
+            let column = match response {
+                "X" => 0,
+                "Y" => 1,
+                "Z" => 2,
+                _ => panic!("Unexpected response"),
+            };
+
+            (them.parse::<Move>().unwrap(), column)
+        })
+        .collect()
+}
+
+fn analyze(input: &str) -> StrategyAnalysis {
+    let guide = guide(input);
+
+    let mut best_score = 0;
+    let mut worst_score = 0;
+    let mut random_expected_score = 0.0;
+    let mut random_score_variance = 0.0;
+
+    for (them, _) in guide.iter() {
+        let scores = Move::ALL.map(|us| round_score(*them, us));
+
+        best_score += scores.iter().max().unwrap();
+        worst_score += scores.iter().min().unwrap();
+
+        // Rounds are independent, so both the means and the variances add up.
+        let mean = scores.iter().sum::<u32>() as f64 / 3.0;
+        random_expected_score += mean;
+        random_score_variance += scores
+            .iter()
+            .map(|&score| (score as f64 - mean).powi(2))
+            .sum::<f64>()
+            / 3.0;
+    }
+
+    let (best_mapping, best_mapping_score) = Move::ALL
+        .into_iter()
+        .permutations(3)
+        .map(|mapping| {
+            let score = guide
+                .iter()
+                .map(|(them, column)| round_score(*them, mapping[*column]))
+                .sum::<u32>();
+
+            ([mapping[0], mapping[1], mapping[2]], score)
+        })
+        .max_by_key(|(_, score)| *score)
+        .unwrap();
+
+    StrategyAnalysis {
+        best_score,
+        worst_score,
+        random_expected_score,
+        random_score_variance,
+        best_mapping,
+        best_mapping_score,
+    }
 }
 
 fn part1(input: &str) -> u32 {
@@ -50,19 +164,7 @@ fn part1(input: &str) -> u32 {
                 .map(|m| m.parse::<Move>().unwrap())
                 .collect();
 
-            let (them, us) = (moves[0], moves[1]);
-
-            match them.partial_cmp(&us) {
-                // Draw.
-                Some(Ordering::Equal) => 3 + us as u32,
-                // We win.
-                Some(Ordering::Less) => 6 + us as u32,
-                // They win.
-                Some(Ordering::Greater) => us as u32,
-                None => {
-                    panic!("Moves should be comparable")
-                }
-            }
+            round_score(moves[0], moves[1])
         })
         .sum()
 }
@@ -123,4 +225,19 @@ C Z";
         let result = part2(INPUT);
         assert_eq!(result, 12);
     }
+
+    #[test]
+    fn analyze_works() {
+        let analysis = analyze(INPUT);
+
+        assert_eq!(analysis.best_score, 24);
+        assert_eq!(analysis.worst_score, 6);
+        assert_eq!(analysis.random_expected_score, 15.0);
+        assert_eq!(analysis.random_score_variance, 20.0);
+        assert_eq!(
+            analysis.best_mapping,
+            [Move::Scissors, Move::Paper, Move::Rock]
+        );
+        assert_eq!(analysis.best_mapping_score, 24);
+    }
 }
@@ -1,57 +1,249 @@
-#![feature(iter_array_chunks)]
-
-use std::collections::HashMap;
+use std::{env, fmt, ops::BitAnd, str::FromStr};
 
 use aoc::read_file_input;
 
+/// A set of rucksack items, stored as one bit per priority (1 through 52).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    fn priority(item: char) -> Option<u32> {
+        match item {
+            'a'..='z' => Some(item as u32 - 'a' as u32 + 1),
+            'A'..='Z' => Some(item as u32 - 'A' as u32 + 27),
+            _ => None,
+        }
+    }
+
+    fn item(priority: u32) -> char {
+        match priority {
+            1..=26 => (b'a' + (priority - 1) as u8) as char,
+            27..=52 => (b'A' + (priority - 27) as u8) as char,
+            _ => panic!("Priority out of range"),
+        }
+    }
+
+    fn insert(&mut self, item: char) -> bool {
+        match ItemSet::priority(item) {
+            Some(priority) => {
+                self.0 |= 1 << priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn intersect_all(sets: impl IntoIterator<Item = ItemSet>) -> ItemSet {
+        sets.into_iter()
+            .reduce(|common, set| common & set)
+            .unwrap_or_default()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = char> {
+        let bits = self.0;
+
+        (1..=52)
+            .filter(move |priority| bits & (1 << priority) != 0)
+            .map(ItemSet::item)
+    }
+}
+
+impl FromStr for ItemSet {
+    type Err = char;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = ItemSet::default();
+
+        for item in s.chars() {
+            if !set.insert(item) {
+                return Err(item);
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+impl BitAnd for ItemSet {
+    type Output = ItemSet;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        ItemSet(self.0 & rhs.0)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Diagnostic {
+    OddLength(usize),
+    InvalidItems(String),
+    NoCommonItem,
+    MultipleCommonItems(String),
+    IncompleteGroup(usize),
+    NoBadge,
+    MultipleBadges(String),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::OddLength(len) => write!(f, "odd number of items ({len})"),
+            Diagnostic::InvalidItems(items) => write!(f, "invalid items {items:?}"),
+            Diagnostic::NoCommonItem => write!(f, "no item in both compartments"),
+            Diagnostic::MultipleCommonItems(items) => {
+                write!(f, "multiple items in both compartments {items:?}")
+            }
+            Diagnostic::IncompleteGroup(len) => write!(f, "group has only {len} sacks"),
+            Diagnostic::NoBadge => write!(f, "group has no badge"),
+            Diagnostic::MultipleBadges(items) => write!(f, "group has multiple badges {items:?}"),
+        }
+    }
+}
+
 fn main() {
     let input = read_file_input("03.txt".to_string());
 
+    if env::args().any(|arg| arg == "--validate") {
+        let diagnostics = validate(&input);
+
+        if diagnostics.is_empty() {
+            println!("  No problems found");
+        }
+
+        for (line_number, diagnostic) in diagnostics {
+            println!("  Line {line_number}: {diagnostic}");
+        }
+
+        return;
+    }
+
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
 }
 
-fn part1(input: &str) -> usize {
-    let letter_scores = ('a'..='z')
-        .chain('A'..='Z')
-        .enumerate()
-        .map(|(idx, c)| (c, idx + 1))
-        .collect::<HashMap<char, usize>>();
+fn compartments(sack: &str) -> (ItemSet, ItemSet) {
+    let (compartment_a_items, compartment_b_items) = sack.split_at(sack.len() / 2);
 
-    let common_item_scores = input.lines().map(|line| {
-        let half_sack_item_count = line.len() / 2;
-        let compartment_a_items = &line[0..half_sack_item_count];
-        let compartment_b_items = &line[half_sack_item_count..(half_sack_item_count * 2)];
+    (
+        compartment_a_items.parse().unwrap(),
+        compartment_b_items.parse().unwrap(),
+    )
+}
 
-        let common_items = compartment_a_items
-            .chars()
-            .find(|c| compartment_b_items.contains(*c))
-            .unwrap();
+/// Items shared by every sack within each consecutive group of `group_size` sacks.
+fn common_items(input: &str, group_size: usize) -> Vec<ItemSet> {
+    let sacks = input
+        .lines()
+        .map(|line| line.parse::<ItemSet>().unwrap())
+        .collect::<Vec<ItemSet>>();
 
-        letter_scores.get(&common_items).unwrap()
-    });
+    sacks
+        .chunks(group_size)
+        .map(|group| ItemSet::intersect_all(group.iter().copied()))
+        .collect()
+}
+
+// Invalid items are reported separately, so leave them out of the set.
+fn valid_items(items: &str) -> ItemSet {
+    let mut set = ItemSet::default();
 
-    common_item_scores.sum::<usize>()
+    for item in items.chars() {
+        set.insert(item);
+    }
+
+    set
 }
 
-fn part2(input: &str) -> usize {
-    let letter_scores = ('a'..='z')
-        .chain('A'..='Z')
-        .enumerate()
-        .map(|(idx, c)| (c, idx + 1))
-        .collect::<HashMap<char, usize>>();
+/// Checks every sack (and every group of three sacks) against the puzzle rules, returning
+/// diagnostics keyed by line number. Group diagnostics use the group's first line.
+fn validate(input: &str) -> Vec<(usize, Diagnostic)> {
+    let mut diagnostics = vec![];
+    let mut sacks = vec![];
+
+    for (i, line) in input.lines().enumerate() {
+        let line_number = i + 1;
 
-    let sack_groups = input.lines().array_chunks::<3>();
+        let len = line.chars().count();
 
-    let priorities = sack_groups.map(|[sack_a, sack_b, sack_c]| {
-        let common_items = sack_a
+        if len % 2 != 0 {
+            diagnostics.push((line_number, Diagnostic::OddLength(len)));
+        }
+
+        let invalid_items = line
             .chars()
-            .find(|item| sack_b.contains(*item) && sack_c.contains(*item))
-            .unwrap();
-        letter_scores.get(&common_items).unwrap()
+            .filter(|item| ItemSet::priority(*item).is_none())
+            .collect::<String>();
+
+        if !invalid_items.is_empty() {
+            diagnostics.push((line_number, Diagnostic::InvalidItems(invalid_items)));
+        }
+
+        // Split on a char boundary, since invalid items may be multi-byte.
+        let half = line
+            .char_indices()
+            .nth(len / 2)
+            .map_or(line.len(), |(i, _)| i);
+        let (compartment_a_items, compartment_b_items) = line.split_at(half);
+        let common = valid_items(compartment_a_items) & valid_items(compartment_b_items);
+
+        match common.len() {
+            0 => diagnostics.push((line_number, Diagnostic::NoCommonItem)),
+            1 => {}
+            _ => diagnostics.push((
+                line_number,
+                Diagnostic::MultipleCommonItems(common.iter().collect()),
+            )),
+        }
+
+        sacks.push(valid_items(line));
+    }
+
+    for (i, group) in sacks.chunks(3).enumerate() {
+        let line_number = i * 3 + 1;
+
+        if group.len() < 3 {
+            diagnostics.push((line_number, Diagnostic::IncompleteGroup(group.len())));
+            continue;
+        }
+
+        let badges = ItemSet::intersect_all(group.iter().copied());
+
+        match badges.len() {
+            0 => diagnostics.push((line_number, Diagnostic::NoBadge)),
+            1 => {}
+            _ => diagnostics.push((
+                line_number,
+                Diagnostic::MultipleBadges(badges.iter().collect()),
+            )),
+        }
+    }
+
+    diagnostics.sort_by_key(|(line_number, _)| *line_number);
+
+    diagnostics
+}
+
+fn part1(input: &str) -> u32 {
+    let common_item_scores = input.lines().map(|line| {
+        let (compartment_a, compartment_b) = compartments(line);
+
+        let common_item = (compartment_a & compartment_b).iter().next().unwrap();
+
+        ItemSet::priority(common_item).unwrap()
     });
 
-    priorities.sum::<usize>()
+    common_item_scores.sum::<u32>()
+}
+
+fn part2(input: &str) -> u32 {
+    let priorities = common_items(input, 3)
+        .into_iter()
+        .map(|badge| ItemSet::priority(badge.iter().next().unwrap()).unwrap());
+
+    priorities.sum::<u32>()
 }
 
 #[cfg(test)]
@@ -76,4 +268,57 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
         let result = part2(INPUT);
         assert_eq!(result, 70);
     }
+
+    #[test]
+    fn item_set_works() {
+        let set = "abcAZ".parse::<ItemSet>().unwrap();
+        let other = "cZzz".parse::<ItemSet>().unwrap();
+
+        assert_eq!(ItemSet::priority('p'), Some(16));
+        assert_eq!(ItemSet::priority('L'), Some(38));
+        assert_eq!(ItemSet::priority('1'), None);
+        assert_eq!("ab1".parse::<ItemSet>(), Err('1'));
+        assert_eq!(set.iter().collect::<String>(), "abcAZ");
+        assert_eq!((set & other).iter().collect::<String>(), "cZ");
+        assert_eq!(
+            ItemSet::intersect_all([set, other, "Z".parse().unwrap()]),
+            "Z".parse().unwrap()
+        );
+        assert_eq!(ItemSet::intersect_all([]), ItemSet::default());
+    }
+
+    #[test]
+    fn validate_works() {
+        assert_eq!(validate(INPUT), vec![]);
+
+        let input = "abcab
+abAB
+ab1cdc
+xé";
+
+        assert_eq!(
+            validate(input),
+            vec![
+                (1, Diagnostic::OddLength(5)),
+                (1, Diagnostic::MultipleCommonItems("ab".to_string())),
+                (1, Diagnostic::MultipleBadges("ab".to_string())),
+                (2, Diagnostic::NoCommonItem),
+                (3, Diagnostic::InvalidItems("1".to_string())),
+                (3, Diagnostic::NoCommonItem),
+                (4, Diagnostic::InvalidItems("é".to_string())),
+                (4, Diagnostic::NoCommonItem),
+                (4, Diagnostic::IncompleteGroup(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn common_items_works() {
+        let pairs = common_items(INPUT, 2)
+            .iter()
+            .map(|items| items.iter().collect::<String>())
+            .collect::<Vec<String>>();
+
+        assert_eq!(pairs, vec!["frsFM", "qvwBT", "GJZ"]);
+    }
 }
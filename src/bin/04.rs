@@ -1,5 +1,5 @@
 use aoc::read_file_input;
-use std::ops::RangeInclusive;
+use std::{collections::BTreeMap, env, ops::RangeInclusive};
 
 use nom::{
     bytes::complete::tag,
@@ -12,67 +12,235 @@ use nom::{
 type SectionAssignment = RangeInclusive<u32>;
 type SectionAssignmentPairs = (SectionAssignment, SectionAssignment);
 
+/// Allen's interval relations, describing the first assignment relative to the second. Sections
+/// are treated as unit-length intervals, so `2-3` meets `4-5` while `2-4` overlaps `4-5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    fn classify(a: &SectionAssignment, b: &SectionAssignment) -> Relation {
+        // Half-open bounds, widened so that `end + 1` cannot overflow.
+        let (a_start, a_end) = (*a.start() as u64, *a.end() as u64 + 1);
+        let (b_start, b_end) = (*b.start() as u64, *b.end() as u64 + 1);
+
+        if a_end < b_start {
+            Relation::Before
+        } else if a_end == b_start {
+            Relation::Meets
+        } else if b_end < a_start {
+            Relation::After
+        } else if b_end == a_start {
+            Relation::MetBy
+        } else if a_start == b_start && a_end == b_end {
+            Relation::Equals
+        } else if a_start == b_start {
+            if a_end < b_end {
+                Relation::Starts
+            } else {
+                Relation::StartedBy
+            }
+        } else if a_end == b_end {
+            if a_start > b_start {
+                Relation::Finishes
+            } else {
+                Relation::FinishedBy
+            }
+        } else if a_start > b_start && a_end < b_end {
+            Relation::During
+        } else if a_start < b_start && a_end > b_end {
+            Relation::Contains
+        } else if a_start < b_start {
+            Relation::Overlaps
+        } else {
+            Relation::OverlappedBy
+        }
+    }
+
+    /// Whether one assignment fully contains the other.
+    fn is_containment(&self) -> bool {
+        matches!(
+            self,
+            Relation::Starts
+                | Relation::During
+                | Relation::Finishes
+                | Relation::Equals
+                | Relation::FinishedBy
+                | Relation::Contains
+                | Relation::StartedBy
+        )
+    }
+
+    /// Whether the assignments share at least one section.
+    fn is_overlap(&self) -> bool {
+        !matches!(
+            self,
+            Relation::Before | Relation::Meets | Relation::MetBy | Relation::After
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Coverage {
+    covered_sections: u64,
+    // Number of sections assigned to exactly `k` elves, keyed by `k`.
+    sections_by_elves: BTreeMap<usize, u64>,
+    // Section with the most elves assigned (the lowest one on ties), with its elf count.
+    most_assigned: Option<(u32, usize)>,
+    // Unassigned sections between the lowest and highest assigned sections.
+    gaps: Vec<SectionAssignment>,
+}
+
 fn main() {
     let input = read_file_input("04.txt".to_string());
 
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
+
+    if env::args().any(|arg| arg == "--relations") {
+        println!();
+
+        for (relation, count) in relation_summary(&input) {
+            println!("  {relation:?}: {count}");
+        }
+    }
+
+    if env::args().any(|arg| arg == "--coverage") {
+        let (_, assignments) = section_assignments(&input).unwrap();
+        let coverage = coverage(&assignments);
+
+        println!();
+        println!("  Covered sections: {}", coverage.covered_sections);
+
+        for (elves, sections) in coverage.sections_by_elves.iter() {
+            println!("  Sections covered by {elves} elves: {sections}");
+        }
+
+        if let Some((section, elves)) = coverage.most_assigned {
+            println!("  Most assigned section: {section} ({elves} elves)");
+        }
+
+        for gap in coverage.gaps.iter() {
+            println!("  Uncovered sections: {}-{}", gap.start(), gap.end());
+        }
+    }
 }
 
 fn sections(input: &str) -> IResult<&str, SectionAssignment> {
-    let (input, (start, end)) = separated_pair(complete::u32, tag("-"), complete::u32)(input)?;
+    let (input, (start, end)) =
+        separated_pair(complete::u32, tag("-"), complete::u32).parse(input)?;
 
     Ok((input, start..=end))
 }
 
 fn line(input: &str) -> IResult<&str, SectionAssignmentPairs> {
-    let (input, (start, end)) = separated_pair(sections, tag(","), sections)(input)?;
+    let (input, (start, end)) = separated_pair(sections, tag(","), sections).parse(input)?;
 
     Ok((input, (start, end)))
 }
 
 fn section_assignments(input: &str) -> IResult<&str, Vec<SectionAssignmentPairs>> {
-    let (input, ranges) = separated_list1(newline, line)(input)?;
+    let (input, ranges) = separated_list1(newline, line).parse(input)?;
 
     Ok((input, ranges))
 }
 
-fn part1(input: &str) -> usize {
+fn relations(input: &str) -> Vec<Relation> {
     let (_, assignments) = section_assignments(input).unwrap();
 
-    let fully_contained_assignments = assignments.iter().filter(|(range_a, range_b)| {
-        let a_contains_b = range_a
-            .clone()
-            .into_iter()
-            .all(|num| range_b.contains(&num));
-        let b_contains_a = range_b
-            .clone()
-            .into_iter()
-            .all(|num| range_a.contains(&num));
-
-        a_contains_b || b_contains_a
-    });
+    assignments
+        .iter()
+        .map(|(range_a, range_b)| Relation::classify(range_a, range_b))
+        .collect()
+}
 
-    fully_contained_assignments.count()
+fn relation_summary(input: &str) -> BTreeMap<Relation, usize> {
+    relations(input)
+        .into_iter()
+        .fold(BTreeMap::new(), |mut summary, relation| {
+            *summary.entry(relation).or_insert(0) += 1;
+            summary
+        })
 }
 
-fn part2(input: &str) -> usize {
-    let (_, assignments) = section_assignments(input).unwrap();
+fn coverage(assignments: &[SectionAssignmentPairs]) -> Coverage {
+    // Sweep over +1/-1 events at each assignment's start and one past its end.
+    let mut events: BTreeMap<u64, i64> = BTreeMap::new();
 
-    let partially_contained_assignments = assignments.iter().filter(|(range_a, range_b)| {
-        let a_contains_b = range_a
-            .clone()
-            .into_iter()
-            .any(|num| range_b.contains(&num));
-        let b_contains_a = range_b
-            .clone()
-            .into_iter()
-            .any(|num| range_a.contains(&num));
+    for range in assignments
+        .iter()
+        .flat_map(|(range_a, range_b)| [range_a, range_b])
+    {
+        if range.is_empty() {
+            continue;
+        }
 
-        a_contains_b || b_contains_a
-    });
+        *events.entry(*range.start() as u64).or_insert(0) += 1;
+        *events.entry(*range.end() as u64 + 1).or_insert(0) -= 1;
+    }
 
-    partially_contained_assignments.count()
+    let mut coverage = Coverage {
+        covered_sections: 0,
+        sections_by_elves: BTreeMap::new(),
+        most_assigned: None,
+        gaps: vec![],
+    };
+    let mut elves = 0;
+
+    for ((&position, &delta), (&next_position, _)) in events.iter().zip(events.iter().skip(1)) {
+        elves += delta;
+
+        let sections = next_position - position;
+
+        if elves == 0 {
+            coverage
+                .gaps
+                .push(position as u32..=(next_position - 1) as u32);
+            continue;
+        }
+
+        coverage.covered_sections += sections;
+        *coverage
+            .sections_by_elves
+            .entry(elves as usize)
+            .or_insert(0) += sections;
+
+        if coverage
+            .most_assigned
+            .is_none_or(|(_, most)| elves as usize > most)
+        {
+            coverage.most_assigned = Some((position as u32, elves as usize));
+        }
+    }
+
+    coverage
+}
+
+fn part1(input: &str) -> usize {
+    relations(input)
+        .iter()
+        .filter(|relation| relation.is_containment())
+        .count()
+}
+
+fn part2(input: &str) -> usize {
+    relations(input)
+        .iter()
+        .filter(|relation| relation.is_overlap())
+        .count()
 }
 
 #[cfg(test)]
@@ -97,4 +265,76 @@ mod tests {
         let result = part2(INPUT);
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn relations_work() {
+        assert_eq!(
+            relations(INPUT),
+            vec![
+                Relation::Before,
+                Relation::Meets,
+                Relation::Overlaps,
+                Relation::Contains,
+                Relation::Finishes,
+                Relation::Overlaps,
+            ]
+        );
+
+        assert_eq!(Relation::classify(&(6..=8), &(2..=4)), Relation::After);
+        assert_eq!(Relation::classify(&(4..=5), &(2..=3)), Relation::MetBy);
+        assert_eq!(
+            Relation::classify(&(7..=9), &(5..=7)),
+            Relation::OverlappedBy
+        );
+        assert_eq!(Relation::classify(&(3..=7), &(2..=8)), Relation::During);
+        assert_eq!(Relation::classify(&(4..=6), &(6..=6)), Relation::FinishedBy);
+        assert_eq!(Relation::classify(&(2..=3), &(2..=6)), Relation::Starts);
+        assert_eq!(Relation::classify(&(2..=6), &(2..=3)), Relation::StartedBy);
+        assert_eq!(Relation::classify(&(2..=6), &(2..=6)), Relation::Equals);
+        assert_eq!(
+            Relation::classify(&(0..=u32::MAX), &(1..=u32::MAX)),
+            Relation::FinishedBy
+        );
+    }
+
+    #[test]
+    fn coverage_works() {
+        let (_, assignments) = section_assignments(INPUT).unwrap();
+
+        assert_eq!(
+            coverage(&assignments),
+            Coverage {
+                covered_sections: 8,
+                sections_by_elves: BTreeMap::from([(1, 1), (4, 2), (5, 1), (6, 1), (7, 2), (8, 1)]),
+                most_assigned: Some((6, 8)),
+                gaps: vec![],
+            }
+        );
+
+        let (_, assignments) = section_assignments("1-2,5-6\n8-8,1-1").unwrap();
+
+        assert_eq!(
+            coverage(&assignments),
+            Coverage {
+                covered_sections: 5,
+                sections_by_elves: BTreeMap::from([(1, 4), (2, 1)]),
+                most_assigned: Some((1, 2)),
+                gaps: vec![3..=4, 7..=7],
+            }
+        );
+    }
+
+    #[test]
+    fn relation_summary_works() {
+        assert_eq!(
+            relation_summary(INPUT),
+            BTreeMap::from([
+                (Relation::Before, 1),
+                (Relation::Meets, 1),
+                (Relation::Overlaps, 2),
+                (Relation::Finishes, 1),
+                (Relation::Contains, 1),
+            ])
+        );
+    }
 }
@@ -1,149 +1,472 @@
-use aoc::read_file_input;
+use aoc::{arg_value, read_file_input};
+use std::{env, fmt, ops::Range};
 
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    character::complete::{self, alpha1, digit1, multispace1, newline, space1},
+    character::complete::{self, alphanumeric1, digit1, multispace1, newline, space0},
     multi::{many1, separated_list1},
-    sequence::{delimited, preceded},
+    sequence::delimited,
     *,
 };
 
-#[derive(Debug)]
+// Stack numbers are kept as written in the input, starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct Move {
     number: u32,
     from: u32,
     to: u32,
 }
 
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.number, self.from, self.to)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    UnknownStack(u32),
+    EmptyStack(u32),
+    NotEnoughCrates {
+        stack: u32,
+        requested: u32,
+        available: usize,
+    },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::UnknownStack(stack) => write!(f, "stack {stack} does not exist"),
+            MoveError::EmptyStack(stack) => write!(f, "stack {stack} is empty"),
+            MoveError::NotEnoughCrates {
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "stack {stack} has {available} crates, but {requested} were requested"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct InvalidMove {
+    // Position of the move in the instruction list, starting at 1.
+    instruction: usize,
+    crate_move: Move,
+    error: MoveError,
+}
+
+impl fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instruction {} ({}): {}",
+            self.instruction, self.crate_move, self.error
+        )
+    }
+}
+
+/// How to handle a move that cannot be carried out on the current stacks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IllegalMoves {
+    Fail,
+    Skip,
+    // Move as many crates as the stack holds, skipping moves involving unknown stacks.
+    Clamp,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CrateStacks<'a> {
+    stacks: Vec<Vec<&'a str>>,
+}
+
+impl<'a> CrateStacks<'a> {
+    fn stack(&self, number: u32) -> Result<&Vec<&'a str>, MoveError> {
+        (number as usize)
+            .checked_sub(1)
+            .and_then(|i| self.stacks.get(i))
+            .ok_or(MoveError::UnknownStack(number))
+    }
+
+    fn check(&self, Move { number, from, to }: &Move) -> Result<(), MoveError> {
+        let available = self.stack(*from)?.len();
+        self.stack(*to)?;
+
+        if available < *number as usize {
+            return Err(if available == 0 {
+                MoveError::EmptyStack(*from)
+            } else {
+                MoveError::NotEnoughCrates {
+                    stack: *from,
+                    requested: *number,
+                    available,
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply(&mut self, crane: &impl Crane, crate_move: &Move) -> Result<(), MoveError> {
+        self.check(crate_move)?;
+
+        let Move { number, from, to } = crate_move;
+        let from_stack = &mut self.stacks[*from as usize - 1];
+
+        let lifted = from_stack
+            .drain((from_stack.len() - *number as usize)..)
+            .collect::<Vec<&str>>();
+
+        self.stacks[*to as usize - 1].extend(crane.arrange(lifted));
+
+        Ok(())
+    }
+
+    /// Applies each move in turn, handling illegal moves according to `illegal_moves`. Returns
+    /// the illegal moves that were skipped or clamped, and calls `on_step` after every move.
+    fn simulate(
+        &mut self,
+        crane: &impl Crane,
+        moves: &[Move],
+        illegal_moves: IllegalMoves,
+        mut on_step: impl FnMut(&Move, &Self),
+    ) -> Result<Vec<InvalidMove>, InvalidMove> {
+        let mut invalid_moves = vec![];
+
+        for (i, crate_move) in moves.iter().enumerate() {
+            if let Err(error) = self.apply(crane, crate_move) {
+                let invalid_move = InvalidMove {
+                    instruction: i + 1,
+                    crate_move: *crate_move,
+                    error,
+                };
+
+                match (illegal_moves, &invalid_move.error) {
+                    (IllegalMoves::Fail, _) => return Err(invalid_move),
+                    (IllegalMoves::Clamp, MoveError::NotEnoughCrates { available, .. }) => {
+                        let clamped_move = Move {
+                            number: *available as u32,
+                            ..*crate_move
+                        };
+
+                        self.apply(crane, &clamped_move).unwrap();
+                    }
+                    _ => {}
+                }
+
+                invalid_moves.push(invalid_move);
+            }
+
+            on_step(crate_move, self);
+        }
+
+        Ok(invalid_moves)
+    }
+
+    // Top-most crate label in each stack, or `None` for empty stacks.
+    fn top_crates(&self) -> Vec<Option<&'a str>> {
+        self.stacks
+            .iter()
+            .map(|stack| stack.last().copied())
+            .collect()
+    }
+}
+
+/// Renders the stacks in the same drawing format used by the puzzle input. Each column is as
+/// wide as its widest crate label (or stack index), with the index centered beneath it.
+impl fmt::Display for CrateStacks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self
+            .stacks
+            .iter()
+            .map(|stack| stack.len())
+            .max()
+            .unwrap_or(0);
+        let widths = self
+            .stacks
+            .iter()
+            .enumerate()
+            .map(|(i, stack)| {
+                stack
+                    .iter()
+                    .map(|label| label.len() + 2)
+                    .chain([3, (i + 1).to_string().len()])
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<usize>>();
+
+        for level in (0..height).rev() {
+            let row = self
+                .stacks
+                .iter()
+                .zip(widths.iter())
+                .map(|(stack, width)| match stack.get(level) {
+                    Some(c) => format!("{:<width$}", format!("[{c}]")),
+                    None => " ".repeat(*width),
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            writeln!(f, "{row}")?;
+        }
+
+        let indexes = widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| format!("{:^width$}", i + 1))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        write!(f, "{indexes}")
+    }
+}
+
+trait Crane {
+    /// Takes crates lifted off a stack (bottom-most first) and returns them in the order they
+    /// are placed onto the destination stack.
+    fn arrange<'a>(&self, crates: Vec<&'a str>) -> Vec<&'a str>;
+}
+
+/// Moves crates one at a time.
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn arrange<'a>(&self, crates: Vec<&'a str>) -> Vec<&'a str> {
+        crates.into_iter().rev().collect()
+    }
+}
+
+/// Moves all crates at once.
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn arrange<'a>(&self, crates: Vec<&'a str>) -> Vec<&'a str> {
+        crates
+    }
+}
+
+/// Moves up to `capacity` crates at once, lifting from the top of the stack each time.
+struct BatchCrane {
+    capacity: usize,
+}
+
+impl Crane for BatchCrane {
+    fn arrange<'a>(&self, crates: Vec<&'a str>) -> Vec<&'a str> {
+        crates
+            .rchunks(self.capacity.max(1))
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
 fn main() {
     let input = read_file_input("05.txt".to_string());
 
-    println!("  Part 1: {}", part1(&input));
-    println!("  Part 2: {}", part2(&input));
-}
+    if env::args().any(|arg| arg == "--validate") {
+        let invalid_moves = validate_moves(&input);
+
+        if invalid_moves.is_empty() {
+            println!("  No problems found");
+        }
 
-fn parse_crate(input: &str) -> IResult<&str, Option<&str>> {
-    let (input, c) = alt((
-        tag("   "),
-        delimited(complete::char('['), alpha1, complete::char(']')),
-    ))(input)?;
+        for invalid_move in invalid_moves {
+            println!("  Illegal {invalid_move}");
+        }
 
-    let result = match c {
-        "   " => None,
-        value => Some(value),
+        return;
+    }
+
+    let illegal_moves = match arg_value("--illegal-moves").as_deref() {
+        None | Some("fail") => IllegalMoves::Fail,
+        Some("skip") => IllegalMoves::Skip,
+        Some("clamp") => IllegalMoves::Clamp,
+        Some(other) => panic!("Unknown illegal move handling {other}"),
+    };
+    let answer = |result: Result<Vec<Option<&str>>, InvalidMove>| match result {
+        Ok(top_crates) => format_top_crates(&top_crates),
+        Err(invalid_move) => format!("illegal {invalid_move}"),
     };
 
-    Ok((input, result))
+    println!(
+        "  Part 1: {}",
+        answer(top_crates(&input, &CrateMover9000, illegal_moves))
+    );
+    println!(
+        "  Part 2: {}",
+        answer(top_crates(&input, &CrateMover9001, illegal_moves))
+    );
+
+    if let Some(capacity) = arg_value("--capacity") {
+        let capacity = capacity.parse().expect("Capacity should be a number");
+
+        println!(
+            "  Capacity {capacity}: {}",
+            answer(top_crates(&input, &BatchCrane { capacity }, illegal_moves))
+        );
+    }
+
+    if let Some(model) = arg_value("--replay") {
+        let (steps, failure) = match model.as_str() {
+            "9000" => replay(&input, &CrateMover9000, illegal_moves),
+            "9001" => replay(&input, &CrateMover9001, illegal_moves),
+            _ => panic!("Unknown crane model {model}"),
+        };
+        let (_, (initial_stacks, _)) = parse_crate_stacks(&input).unwrap();
+
+        println!("\n{initial_stacks}");
+
+        for (crate_move, crate_stacks) in steps {
+            println!("\n{crate_move}\n\n{crate_stacks}");
+        }
+
+        if let Some(invalid_move) = failure {
+            println!("\nStopped at illegal {invalid_move}");
+        }
+    }
+}
+
+// Column span of a crate or stack index within its row, used to line crates up with stacks.
+type Span = Range<usize>;
+
+fn parse_crate(input: &str) -> IResult<&str, &str> {
+    let (input, label) =
+        delimited(complete::char('['), alphanumeric1, complete::char(']')).parse(input)?;
+
+    Ok((input, label))
+}
+
+/// Parses `item`s separated by any amount of space, recording where each one sits in the row.
+fn spanned<'a, O>(
+    mut item: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<(Span, O)>> {
+    move |row: &'a str| {
+        let (input, items) = many1(|input: &'a str| {
+            let (input, _) = space0(input)?;
+            let start = row.len() - input.len();
+            let (input, value) = item(input)?;
+
+            Ok((input, (start..row.len() - input.len(), value)))
+        })
+        .parse(row)?;
+        let (input, _) = space0(input)?;
+
+        Ok((input, items))
+    }
 }
 
-fn parse_crates(input: &str) -> IResult<&str, Vec<Option<&str>>> {
-    let (input, result) = separated_list1(tag(" "), parse_crate)(input)?;
+fn parse_crates(input: &str) -> IResult<&str, Vec<(Span, &str)>> {
+    let (input, result) = spanned(parse_crate).parse(input)?;
 
     Ok((input, result))
 }
 
 fn parse_move_instruction(input: &str) -> IResult<&str, Move> {
-    let (input, _) = tag("move ")(input)?;
+    let (input, _) = tag("move ").parse(input)?;
     let (input, number) = complete::u32(input)?;
-    let (input, _) = tag(" from ")(input)?;
+    let (input, _) = tag(" from ").parse(input)?;
     let (input, from) = complete::u32(input)?;
-    let (input, _) = tag(" to ")(input)?;
+    let (input, _) = tag(" to ").parse(input)?;
     let (input, to) = complete::u32(input)?;
 
-    Ok((
-        input,
-        Move {
-            number,
-            from: from - 1,
-            to: to - 1,
-        },
-    ))
+    Ok((input, Move { number, from, to }))
 }
 
-fn parse_crate_stacks(input: &str) -> IResult<&str, (Vec<Vec<&str>>, Vec<Move>)> {
-    let (input, horizontal_crates) = separated_list1(newline, parse_crates)(input)?;
+fn parse_crate_stacks(input: &str) -> IResult<&str, (CrateStacks<'_>, Vec<Move>)> {
+    let (input, horizontal_crates) = separated_list1(newline, parse_crates).parse(input)?;
     // Newline after horizontal crates.
     let (input, _) = newline(input)?;
     // Crate stack indexes.
-    let (input, _) = many1(preceded(space1, digit1))(input)?;
+    let (input, indexes) = spanned(digit1).parse(input)?;
     // Newlines after crate stack indexes.
     let (input, _) = multispace1(input)?;
-    let (input, moves) = separated_list1(newline, parse_move_instruction)(input)?;
-
-    let mut vertical_crates: Vec<Vec<Option<&str>>> = vec![];
-
-    // Initialize vertical crates vector.
-    for _ in 0..=horizontal_crates.len() {
-        vertical_crates.push(vec![]);
-    }
-
-    // Fill vertical crates vector.
-    for vec in horizontal_crates.iter().rev() {
-        for (i, c) in vec.iter().enumerate() {
-            vertical_crates[i].push(*c)
+    let (input, moves) = separated_list1(newline, parse_move_instruction).parse(input)?;
+
+    // Initialize one stack per index.
+    let mut stacks: Vec<Vec<&str>> = vec![vec![]; indexes.len()];
+
+    // Fill stacks from the bottom up, placing each crate on the stack whose index is centered
+    // closest to it. Spans are compared by `start + end` to stay in whole numbers.
+    for row in horizontal_crates.iter().rev() {
+        for (span, label) in row.iter() {
+            let (i, _) = indexes
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (index_span, _))| {
+                    (index_span.start + index_span.end).abs_diff(span.start + span.end)
+                })
+                .unwrap();
+
+            stacks[i].push(label);
         }
     }
 
-    // Finalize crate stacks by removing empty crate locations.
-    let final_crates: Vec<Vec<&str>> = vertical_crates
-        .iter()
-        .map(|vec| vec.iter().filter_map(|v| *v).collect())
-        .collect();
-
-    Ok((input, (final_crates, moves)))
+    Ok((input, (CrateStacks { stacks }, moves)))
 }
 
-pub fn part1(input: &str) -> String {
+/// Records the state of the stacks after each move, along with the illegal move that stopped
+/// the replay, if any.
+fn replay<'a>(
+    input: &'a str,
+    crane: &impl Crane,
+    illegal_moves: IllegalMoves,
+) -> (Vec<(Move, CrateStacks<'a>)>, Option<InvalidMove>) {
     let (_, (mut crate_stacks, moves)) = parse_crate_stacks(input).unwrap();
+    let mut steps = vec![];
 
-    for Move { number, from, to } in moves.iter() {
-        let crate_stack_len = crate_stacks[*from as usize].len();
+    let result = crate_stacks.simulate(crane, &moves, illegal_moves, |crate_move, stacks| {
+        steps.push((*crate_move, stacks.clone()))
+    });
 
-        let crate_stack = crate_stacks[*from as usize]
-            .drain((crate_stack_len - *number as usize)..)
-            .rev()
-            .collect::<Vec<&str>>();
+    (steps, result.err())
+}
 
-        for c in crate_stack.iter() {
-            crate_stacks[*to as usize].push(c);
-        }
-    }
+/// Reports every illegal move, skipping each one so that later moves are still checked.
+fn validate_moves(input: &str) -> Vec<InvalidMove> {
+    let (_, (mut crate_stacks, moves)) = parse_crate_stacks(input).unwrap();
 
-    // Build string based on top-most crate in each stack.
+    // Stack heights do not depend on the crane model, so any model will do.
     crate_stacks
-        .iter()
-        .map(|v| match v.iter().last() {
-            Some(c) => c,
-            None => "",
-        })
-        .collect()
+        .simulate(&CrateMover9001, &moves, IllegalMoves::Skip, |_, _| {})
+        .unwrap()
 }
 
-pub fn part2(input: &str) -> String {
+fn top_crates<'a>(
+    input: &'a str,
+    crane: &impl Crane,
+    illegal_moves: IllegalMoves,
+) -> Result<Vec<Option<&'a str>>, InvalidMove> {
     let (_, (mut crate_stacks, moves)) = parse_crate_stacks(input).unwrap();
 
-    for Move { number, from, to } in moves.iter() {
-        let crate_stack_len = crate_stacks[*from as usize].len();
+    crate_stacks.simulate(crane, &moves, illegal_moves, |_, _| {})?;
 
-        let crate_stack = crate_stacks[*from as usize]
-            .drain((crate_stack_len - *number as usize)..)
-            .collect::<Vec<&str>>();
+    Ok(crate_stacks.top_crates())
+}
 
-        for c in crate_stack.iter() {
-            crate_stacks[*to as usize].push(c);
-        }
+/// Concatenates single-character labels as the puzzle expects. Longer labels are separated by
+/// spaces instead, with `-` marking empty stacks.
+fn format_top_crates(top_crates: &[Option<&str>]) -> String {
+    if top_crates.iter().flatten().all(|label| label.len() == 1) {
+        top_crates.iter().flatten().copied().collect()
+    } else {
+        top_crates
+            .iter()
+            .map(|label| label.unwrap_or("-"))
+            .collect::<Vec<&str>>()
+            .join(" ")
     }
+}
 
-    let result: String = crate_stacks
-        .iter()
-        .map(|v| match v.iter().last() {
-            Some(c) => c,
-            None => "",
-        })
-        .collect();
+pub fn part1(input: &str) -> String {
+    format_top_crates(&top_crates(input, &CrateMover9000, IllegalMoves::Fail).unwrap())
+}
 
-    result
+pub fn part2(input: &str) -> String {
+    format_top_crates(&top_crates(input, &CrateMover9001, IllegalMoves::Fail).unwrap())
 }
 
 #[cfg(test)]
@@ -171,4 +494,186 @@ move 1 from 1 to 2";
         let result = part2(INPUT);
         assert_eq!(result, "MCD");
     }
+
+    #[test]
+    fn render_round_trips() {
+        let (_, (crate_stacks, _)) = parse_crate_stacks(INPUT).unwrap();
+        let (header, _) = INPUT.split_once("\n\n").unwrap();
+
+        assert_eq!(crate_stacks.to_string(), header);
+    }
+
+    #[test]
+    fn replay_works() {
+        let (steps, failure) = replay(INPUT, &CrateMover9000, IllegalMoves::Fail);
+
+        assert_eq!(failure, None);
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0].0.to_string(), "move 1 from 2 to 1");
+        assert_eq!(
+            steps[0].1.to_string(),
+            "[D]        
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 "
+        );
+        assert_eq!(
+            steps[1].1.to_string(),
+            "        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3 "
+        );
+        assert_eq!(
+            steps[3].1.top_crates(),
+            vec![Some("C"), Some("M"), Some("Z")]
+        );
+    }
+
+    #[test]
+    fn batch_crane_works() {
+        let top_crates = |capacity| {
+            format_top_crates(
+                &top_crates(INPUT, &BatchCrane { capacity }, IllegalMoves::Fail).unwrap(),
+            )
+        };
+
+        assert_eq!(top_crates(1), "CMZ");
+        assert_eq!(top_crates(2), "MCZ");
+        assert_eq!(top_crates(3), "MCD");
+    }
+
+    #[test]
+    fn batch_crane_arranges_in_lifts() {
+        let crane = BatchCrane { capacity: 2 };
+
+        assert_eq!(
+            crane.arrange(vec!["A", "B", "C", "D", "E"]),
+            vec!["D", "E", "B", "C", "A"]
+        );
+    }
+
+    const ILLEGAL_INPUT: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 3 from 1 to 3
+move 1 from 1 to 2
+move 1 from 4 to 1
+move 4 from 2 to 0
+move 2 from 3 to 1";
+
+    #[test]
+    fn validate_moves_works() {
+        assert_eq!(validate_moves(INPUT), vec![]);
+        assert_eq!(
+            validate_moves(ILLEGAL_INPUT),
+            vec![
+                InvalidMove {
+                    instruction: 1,
+                    crate_move: Move {
+                        number: 3,
+                        from: 1,
+                        to: 3
+                    },
+                    error: MoveError::NotEnoughCrates {
+                        stack: 1,
+                        requested: 3,
+                        available: 2
+                    },
+                },
+                InvalidMove {
+                    instruction: 3,
+                    crate_move: Move {
+                        number: 1,
+                        from: 4,
+                        to: 1
+                    },
+                    error: MoveError::UnknownStack(4),
+                },
+                InvalidMove {
+                    instruction: 4,
+                    crate_move: Move {
+                        number: 4,
+                        from: 2,
+                        to: 0
+                    },
+                    error: MoveError::UnknownStack(0),
+                },
+                InvalidMove {
+                    instruction: 5,
+                    crate_move: Move {
+                        number: 2,
+                        from: 3,
+                        to: 1
+                    },
+                    error: MoveError::NotEnoughCrates {
+                        stack: 3,
+                        requested: 2,
+                        available: 1
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn illegal_moves_work() {
+        let failure = top_crates(ILLEGAL_INPUT, &CrateMover9000, IllegalMoves::Fail).unwrap_err();
+
+        assert_eq!(
+            failure.to_string(),
+            "instruction 1 (move 3 from 1 to 3): stack 1 has 2 crates, but 3 were requested"
+        );
+        assert_eq!(
+            top_crates(ILLEGAL_INPUT, &CrateMover9000, IllegalMoves::Skip).unwrap(),
+            vec![Some("Z"), Some("N"), Some("P")]
+        );
+        assert_eq!(
+            top_crates(ILLEGAL_INPUT, &CrateMover9000, IllegalMoves::Clamp).unwrap(),
+            vec![Some("N"), Some("D"), Some("P")]
+        );
+    }
+
+    #[test]
+    fn empty_stack_is_reported() {
+        let (_, (mut crate_stacks, _)) = parse_crate_stacks(INPUT).unwrap();
+        let crate_move = Move {
+            number: 1,
+            from: 1,
+            to: 2,
+        };
+
+        crate_stacks.stacks[0].clear();
+
+        assert_eq!(
+            crate_stacks.apply(&CrateMover9000, &crate_move),
+            Err(MoveError::EmptyStack(1))
+        );
+    }
+
+    const WIDE_INPUT: &str = "[AB]                                       
+[10] [X]                             [LONG]
+[C]  [Y]     [D] [D] [D] [D] [D] [D] [Q]   
+ 1    2   3   4   5   6   7   8   9    10  
+
+move 1 from 10 to 3
+move 2 from 1 to 10";
+
+    #[test]
+    fn wide_stacks_work() {
+        let (_, (crate_stacks, _)) = parse_crate_stacks(WIDE_INPUT).unwrap();
+        let (header, _) = WIDE_INPUT.split_once("\n\n").unwrap();
+
+        assert_eq!(crate_stacks.stacks.len(), 10);
+        assert_eq!(crate_stacks.stacks[0], vec!["C", "10", "AB"]);
+        assert_eq!(crate_stacks.stacks[2], Vec::<&str>::new());
+        assert_eq!(crate_stacks.stacks[9], vec!["Q", "LONG"]);
+        assert_eq!(crate_stacks.to_string(), header);
+
+        assert_eq!(part1(WIDE_INPUT), "C X LONG D D D D D D 10");
+        assert_eq!(format_top_crates(&[Some("AB"), None]), "AB -");
+    }
 }
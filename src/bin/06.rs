@@ -1,44 +1,223 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::VecDeque,
+    env,
+    io::{self, Read},
+    ops::Range,
+};
 
-use aoc::read_file_input;
+use aoc::{open_file_input, read_file_input};
+
+/// Tracks the last `k` bytes of a stream and whether they are all distinct, in constant time
+/// per byte.
+struct MarkerDetector {
+    window_size: usize,
+    window: VecDeque<u8>,
+    counts: [usize; 256],
+    // Number of byte values that appear more than once in the window.
+    duplicates: usize,
+}
+
+impl MarkerDetector {
+    fn new(window_size: usize) -> Self {
+        assert!(window_size > 0, "Marker length should be positive");
+
+        MarkerDetector {
+            window_size,
+            window: VecDeque::with_capacity(window_size),
+            counts: [0; 256],
+            duplicates: 0,
+        }
+    }
+
+    /// Adds the next byte, returning whether the window now forms a marker.
+    fn push(&mut self, byte: u8) -> bool {
+        if self.window.len() == self.window_size {
+            let oldest = self.window.pop_front().unwrap() as usize;
+
+            self.counts[oldest] -= 1;
+            if self.counts[oldest] == 1 {
+                self.duplicates -= 1;
+            }
+        }
+
+        self.window.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+
+        self.window.len() == self.window_size && self.duplicates == 0
+    }
+
+    fn clear(&mut self) {
+        self.window.clear();
+        self.counts = [0; 256];
+        self.duplicates = 0;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FrameKind {
+    Packet,
+    Message,
+}
+
+/// A span of the stream that starts with a marker and runs up to the next marker of the same
+/// kind (or the end of the stream). Offsets are byte positions from the start of the stream.
+#[derive(Debug, PartialEq)]
+struct Frame {
+    kind: FrameKind,
+    marker: Range<usize>,
+    end: usize,
+}
+
+impl Frame {
+    fn payload(&self) -> Range<usize> {
+        self.marker.end..self.end
+    }
+}
+
+/// Splits a byte stream into packet and message frames as it is read, holding only the current
+/// marker windows and one read buffer in memory. Markers of the same kind never overlap: after
+/// one is found, the next must be made up entirely of bytes that follow it.
+struct FrameDecoder<R: Read> {
+    reader: R,
+    buffer: [u8; 4096],
+    offset: usize,
+    detectors: [(FrameKind, MarkerDetector, Option<Range<usize>>); 2],
+    frames: VecDeque<Frame>,
+    finished: bool,
+}
+
+impl<R: Read> FrameDecoder<R> {
+    fn new(reader: R) -> Self {
+        FrameDecoder::with_marker_lengths(reader, 4, 14)
+    }
+
+    fn with_marker_lengths(reader: R, packet_marker_len: usize, message_marker_len: usize) -> Self {
+        FrameDecoder {
+            reader,
+            buffer: [0; 4096],
+            offset: 0,
+            detectors: [
+                (
+                    FrameKind::Packet,
+                    MarkerDetector::new(packet_marker_len),
+                    None,
+                ),
+                (
+                    FrameKind::Message,
+                    MarkerDetector::new(message_marker_len),
+                    None,
+                ),
+            ],
+            frames: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.offset += 1;
+
+        for (kind, detector, open_marker) in self.detectors.iter_mut() {
+            if detector.push(byte) {
+                let marker = (self.offset - detector.window_size)..self.offset;
+
+                if let Some(previous_marker) = open_marker.replace(marker.clone()) {
+                    self.frames.push_back(Frame {
+                        kind: *kind,
+                        marker: previous_marker,
+                        end: marker.start,
+                    });
+                }
+
+                detector.clear();
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        for (kind, _, open_marker) in self.detectors.iter_mut() {
+            if let Some(marker) = open_marker.take() {
+                self.frames.push_back(Frame {
+                    kind: *kind,
+                    marker,
+                    end: self.offset,
+                });
+            }
+        }
+
+        self.finished = true;
+    }
+}
+
+impl<R: Read> Iterator for FrameDecoder<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.frames.is_empty() && !self.finished {
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => self.finish(),
+                Ok(len) => {
+                    for i in 0..len {
+                        self.push(self.buffer[i]);
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        self.frames.pop_front().map(Ok)
+    }
+}
 
 fn main() {
     let input = read_file_input("06.txt".to_string());
 
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
-}
 
-pub fn part1(input: &str) -> usize {
-    let window_size = 4;
-    let chars = input.chars().collect::<Vec<char>>();
+    if env::args().any(|arg| arg == "--frames") {
+        println!();
 
-    let datastream_buffer = chars
-        .windows(window_size)
-        .enumerate()
-        .find(|(_i, window)| {
-            let unique_chars = window.iter().collect::<BTreeSet<&char>>();
-            window.len() == unique_chars.len()
-        })
-        .unwrap();
+        for frame in FrameDecoder::new(open_file_input("06.txt".to_string())) {
+            let frame = frame.unwrap();
+            let payload = frame.payload();
 
-    datastream_buffer.0 + window_size
+            println!(
+                "  {:?} at {}..{} (payload {}..{})",
+                frame.kind, frame.marker.start, frame.end, payload.start, payload.end
+            );
+        }
+    }
 }
 
-pub fn part2(input: &str) -> usize {
-    let window_size = 14;
-    let chars = input.chars().collect::<Vec<char>>();
+/// Positions just past every window of `window_size` distinct bytes.
+fn find_markers(stream: &[u8], window_size: usize) -> impl Iterator<Item = usize> + '_ {
+    let mut detector = MarkerDetector::new(window_size);
 
-    let datastream_buffer = chars
-        .windows(window_size)
+    stream
+        .iter()
         .enumerate()
-        .find(|(_i, window)| {
-            let unique_chars = window.iter().collect::<BTreeSet<&char>>();
-            window.len() == unique_chars.len()
-        })
-        .unwrap();
+        .filter(move |(_, &byte)| detector.push(byte))
+        .map(|(i, _)| i + 1)
+}
 
-    datastream_buffer.0 + window_size
+/// Position just past the first window of `window_size` distinct bytes.
+fn find_marker(stream: &[u8], window_size: usize) -> Option<usize> {
+    find_markers(stream, window_size).next()
+}
+
+pub fn part1(input: &str) -> usize {
+    find_marker(input.as_bytes(), 4).unwrap()
+}
+
+pub fn part2(input: &str) -> usize {
+    find_marker(input.as_bytes(), 14).unwrap()
 }
 
 #[cfg(test)]
@@ -62,4 +241,83 @@ mod tests {
         assert_eq!(part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
         assert_eq!(part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
     }
+
+    #[test]
+    fn find_markers_works() {
+        assert_eq!(
+            find_markers(b"aabcaab", 3).collect::<Vec<usize>>(),
+            vec![4, 5]
+        );
+        assert_eq!(
+            find_markers(b"abcd", 1).collect::<Vec<usize>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(find_marker(b"aaaa", 2), None);
+        assert_eq!(find_marker(b"ab", 3), None);
+    }
+
+    // Hands out one byte per read, to exercise frames that span reads.
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((byte, rest)) if !buf.is_empty() => {
+                    buf[0] = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn frame_decoder_works() {
+        let stream = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let frames = FrameDecoder::new(ByteReader(stream))
+            .collect::<io::Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 7);
+        assert_eq!(
+            frames[0],
+            Frame {
+                kind: FrameKind::Packet,
+                marker: 3..7,
+                end: 7
+            }
+        );
+        assert_eq!(frames[5].payload(), 27..30);
+        assert_eq!(
+            frames[6],
+            Frame {
+                kind: FrameKind::Message,
+                marker: 5..19,
+                end: 30
+            }
+        );
+        assert_eq!(
+            FrameDecoder::new(&stream[..])
+                .collect::<io::Result<Vec<Frame>>>()
+                .unwrap(),
+            frames
+        );
+    }
+
+    #[test]
+    fn frame_decoder_marker_lengths_work() {
+        let frames = FrameDecoder::with_marker_lengths(&b"aabcaab"[..], 3, 4)
+            .collect::<io::Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(
+            frames,
+            vec![Frame {
+                kind: FrameKind::Packet,
+                marker: 1..4,
+                end: 7
+            }]
+        );
+    }
 }
@@ -1,14 +1,13 @@
-#![feature(iter_intersperse)]
-
-use aoc::read_file_input;
-use std::collections::BTreeMap;
+use aoc::{arg_value, read_file_input};
+use std::{collections::BTreeMap, env};
 
 use nom::{
     branch::alt,
-    bytes::complete::{is_a, tag},
-    character::complete::{alpha1, newline},
-    multi::separated_list1,
-    sequence::separated_pair,
+    bytes::complete::tag,
+    character::complete::{line_ending, multispace0, not_line_ending, space0, space1},
+    combinator::{all_consuming, eof, peek, verify},
+    multi::{many0, separated_list1},
+    sequence::{preceded, separated_pair, terminated},
     *,
 };
 
@@ -16,19 +15,173 @@ use nom::{
 enum Operation<'a> {
     Cd(Cd<'a>),
     Ls(Vec<Files<'a>>),
+    // Any other command, skipped along with its output.
+    Unknown(&'a str),
 }
 
 #[derive(Debug)]
 enum Cd<'a> {
     Root,
     Up,
-    Down(&'a str),
+    // Any other path, absolute when it starts with `/`, including single names and `.`.
+    Path(&'a str),
 }
 
 #[derive(Debug)]
 enum Files<'a> {
-    File { size: u32 },
+    File { name: &'a str, size: u64 },
     Dir(&'a str),
+    // Any other output line, such as `total 12` from `ls -l`.
+    Unknown(&'a str),
+}
+
+type DirId = usize;
+
+#[derive(Debug, Default)]
+struct Directory<'a> {
+    name: &'a str,
+    parent: Option<DirId>,
+    dirs: BTreeMap<&'a str, DirId>,
+    files: BTreeMap<&'a str, u64>,
+}
+
+/// Directory tree rebuilt from a terminal log. Directories live in an arena and refer to each
+/// other by index, with the root at index 0.
+#[derive(Debug)]
+struct FileSystem<'a> {
+    dirs: Vec<Directory<'a>>,
+}
+
+const ROOT: DirId = 0;
+
+impl<'a> FileSystem<'a> {
+    fn from_operations(operations: &[Operation<'a>]) -> Self {
+        let mut fs = FileSystem {
+            dirs: vec![Directory {
+                name: "/",
+                ..Default::default()
+            }],
+        };
+        let mut cwd = ROOT;
+
+        for operation in operations.iter() {
+            match operation {
+                Operation::Cd(Cd::Root) => cwd = ROOT,
+                Operation::Cd(Cd::Up) => cwd = fs.parent(cwd).unwrap_or(ROOT),
+                Operation::Cd(Cd::Path(path)) => {
+                    if path.starts_with('/') {
+                        cwd = ROOT;
+                    }
+
+                    for name in path.split('/').filter(|name| !name.is_empty()) {
+                        cwd = match name {
+                            "." => cwd,
+                            ".." => fs.parent(cwd).unwrap_or(ROOT),
+                            name => fs.mkdir(cwd, name),
+                        };
+                    }
+                }
+                Operation::Ls(files) => {
+                    // Entries are upserted by name, so listing a directory again doesn't double
+                    // count its files. Entries missing from a later listing are kept.
+                    for file in files.iter() {
+                        match file {
+                            Files::File { name, size } => {
+                                fs.dirs[cwd].files.insert(name, *size);
+                            }
+                            Files::Dir(name) => {
+                                fs.mkdir(cwd, name);
+                            }
+                            Files::Unknown(_) => {}
+                        }
+                    }
+                }
+                Operation::Unknown(_) => {}
+            }
+        }
+
+        fs
+    }
+
+    fn mkdir(&mut self, parent: DirId, name: &'a str) -> DirId {
+        if let Some(&id) = self.dirs[parent].dirs.get(name) {
+            return id;
+        }
+
+        let id = self.dirs.len();
+
+        self.dirs.push(Directory {
+            name,
+            parent: Some(parent),
+            ..Default::default()
+        });
+        self.dirs[parent].dirs.insert(name, id);
+
+        id
+    }
+
+    fn dir(&self, id: DirId) -> &Directory<'a> {
+        &self.dirs[id]
+    }
+
+    fn parent(&self, id: DirId) -> Option<DirId> {
+        self.dirs[id].parent
+    }
+
+    /// Finds a directory by absolute path, such as `/a/e`.
+    fn lookup(&self, path: &str) -> Option<DirId> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(ROOT, |id, name| match name {
+                "." => Some(id),
+                ".." => Some(self.parent(id).unwrap_or(ROOT)),
+                name => self.dirs[id].dirs.get(name).copied(),
+            })
+    }
+
+    fn path(&self, id: DirId) -> String {
+        let mut names = vec![];
+        let mut current = id;
+
+        while let Some(parent) = self.parent(current) {
+            names.push(self.dirs[current].name);
+            current = parent;
+        }
+
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    /// Directories in depth-first order, starting from the root, with children in name order.
+    fn walk(&self) -> impl Iterator<Item = DirId> + '_ {
+        let mut stack = vec![ROOT];
+
+        std::iter::from_fn(move || {
+            let id = stack.pop()?;
+            stack.extend(self.dirs[id].dirs.values().rev());
+
+            Some(id)
+        })
+    }
+
+    /// Total size of every directory, including its subdirectories, indexed by directory.
+    fn sizes(&self) -> Vec<u64> {
+        let mut sizes = self
+            .dirs
+            .iter()
+            .map(|dir| dir.files.values().sum())
+            .collect::<Vec<u64>>();
+
+        // Children are always created after their parents, so walking backwards visits every
+        // child before its parent.
+        for id in (1..self.dirs.len()).rev() {
+            let parent = self.dirs[id].parent.unwrap();
+            sizes[parent] += sizes[id];
+        }
+
+        sizes
+    }
 }
 
 fn main() {
@@ -36,115 +189,472 @@ fn main() {
 
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
+
+    let cmds = commands(&input).unwrap().1;
+    let fs = FileSystem::from_operations(&cmds);
+    let sizes = fs.sizes();
+
+    for cmd in cmds.iter() {
+        match cmd {
+            Operation::Unknown(command) => {
+                eprintln!("warning: skipped unknown command {command:?}");
+            }
+            Operation::Ls(files) => {
+                for file in files.iter() {
+                    if let Files::Unknown(line) = file {
+                        eprintln!("warning: skipped unknown ls output {line:?}");
+                    }
+                }
+            }
+            Operation::Cd(_) => {}
+        }
+    }
+
+    if env::args().any(|arg| arg == "--tree") {
+        println!("\n{}", render_tree(&fs));
+    }
+
+    if env::args().any(|arg| arg == "--du") {
+        println!();
+
+        for (size, path) in disk_usage(&fs) {
+            println!("{}\t{path}", human_size(size));
+        }
+    }
+
+    if let Some(count) = arg_value("--top") {
+        let count = count.parse().expect("Count should be a number");
+
+        println!();
+
+        for (i, (size, path)) in disk_usage(&fs).into_iter().take(count).enumerate() {
+            println!("  {:>2}. {path} {size} ({})", i + 1, human_size(size));
+        }
+    }
+
+    if env::args().any(|arg| arg == "--plan") {
+        let capacity = arg_value("--capacity").map_or(70_000_000, |capacity| {
+            capacity.parse().expect("Capacity should be a number")
+        });
+        let required = arg_value("--required").map_or(30_000_000, |required| {
+            required.parse().expect("Required space should be a number")
+        });
+        let plan = plan_cleanup(&fs, capacity, required);
+
+        println!();
+        println!("  Need to free: {}", plan.need_to_free);
+
+        if let Some((id, size)) = plan.single {
+            println!(
+                "  Single directory: {} {size} (surplus {})",
+                fs.path(id),
+                size - plan.need_to_free
+            );
+        }
+
+        match plan.combined {
+            Some((dirs, size)) => {
+                let paths = dirs.iter().map(|id| fs.path(*id)).collect::<Vec<String>>();
+
+                println!(
+                    "  Combined: {} {size} (surplus {})",
+                    paths.join(", "),
+                    size - plan.need_to_free
+                );
+
+                if plan.block_size > 1 {
+                    println!(
+                        "  Combined plan is approximate, sizes were counted in {}-byte blocks",
+                        plan.block_size
+                    );
+                }
+            }
+            None => println!("  Not enough space can be freed"),
+        }
+    }
+
+    if let Some(path) = arg_value("--ls") {
+        println!();
+
+        match fs.lookup(&path) {
+            Some(id) => {
+                let dir = fs.dir(id);
+
+                for (name, child) in dir.dirs.iter() {
+                    println!("  dir {name} ({})", sizes[*child]);
+                }
+
+                for (name, size) in dir.files.iter() {
+                    println!("  {size} {name}");
+                }
+            }
+            None => println!("  No such directory {path}"),
+        }
+    }
 }
 
-fn file(input: &str) -> IResult<&str, Files> {
-    let (input, (size, _)) = separated_pair(
-        nom::character::complete::u32,
-        tag(" "),
-        is_a("qwertyuiopasdfghjklzxcvbnm."),
-    )(input)?;
+// Rest of the line, which may contain spaces.
+fn name(input: &str) -> IResult<&str, &str> {
+    let (input, name) =
+        verify(not_line_ending, |name: &str| !name.trim().is_empty()).parse(input)?;
 
-    Ok((input, Files::File { size }))
+    Ok((input, name))
 }
 
-fn directory(input: &str) -> IResult<&str, Files> {
-    let (input, _) = tag("dir ")(input)?;
-    let (input, name) = alpha1(input)?;
+// Makes sure a command has no trailing arguments, so `$ ls` does not match `$ lsblk`.
+fn end_of_command(input: &str) -> IResult<&str, ()> {
+    let (input, _) = space0(input)?;
+    let (input, _) = peek(alt((line_ending, eof))).parse(input)?;
+
+    Ok((input, ()))
+}
+
+fn file(input: &str) -> IResult<&str, Files<'_>> {
+    let (input, (size, name)) =
+        separated_pair(nom::character::complete::u64, space1, name).parse(input)?;
+
+    Ok((input, Files::File { name, size }))
+}
+
+fn directory(input: &str) -> IResult<&str, Files<'_>> {
+    let (input, _) = tag("dir ").parse(input)?;
+    let (input, name) = name(input)?;
 
     Ok((input, Files::Dir(name)))
 }
 
-fn ls(input: &str) -> IResult<&str, Operation> {
-    let (input, _) = tag("$ ls")(input)?;
-    let (input, _) = newline(input)?;
-    let (input, files) = separated_list1(newline, alt((file, directory)))(input)?;
+// An output line that isn't blank or a command.
+fn unknown_output(input: &str) -> IResult<&str, Files<'_>> {
+    let (input, line) = verify(not_line_ending, |line: &str| {
+        !line.trim().is_empty() && !line.starts_with('$')
+    })
+    .parse(input)?;
+
+    Ok((input, Files::Unknown(line)))
+}
+
+fn ls(input: &str) -> IResult<&str, Operation<'_>> {
+    let (input, _) = tag("$ ls").parse(input)?;
+    let (input, _) = end_of_command(input)?;
+    let (input, files) = many0(preceded(
+        line_ending,
+        alt((file, directory, unknown_output)),
+    ))
+    .parse(input)?;
 
     Ok((input, Operation::Ls(files)))
 }
 
-fn cd(input: &str) -> IResult<&str, Operation> {
-    let (input, _) = tag("$ cd ")(input)?;
-    let (input, dir) = alt((tag("/"), tag(".."), alpha1))(input)?;
-    let op = match dir {
+fn cd(input: &str) -> IResult<&str, Operation<'_>> {
+    let (input, _) = tag("$ cd ").parse(input)?;
+    let (input, dir) = name(input)?;
+    let op = match dir.trim_end() {
         "/" => Operation::Cd(Cd::Root),
         ".." => Operation::Cd(Cd::Up),
-        name => Operation::Cd(Cd::Down(name)),
+        path => Operation::Cd(Cd::Path(path)),
     };
 
     Ok((input, op))
 }
 
-fn commands(input: &str) -> IResult<&str, Vec<Operation>> {
-    let (input, cmds) = separated_list1(newline, alt((ls, cd)))(input)?;
+fn unknown(input: &str) -> IResult<&str, Operation<'_>> {
+    let (input, _) = tag("$ ").parse(input)?;
+    let (input, command) = not_line_ending(input)?;
+    // Output lines, up to the next command.
+    let (input, _) = many0(preceded(
+        line_ending,
+        verify(not_line_ending, |line: &str| !line.starts_with('$')),
+    ))
+    .parse(input)?;
+
+    Ok((input, Operation::Unknown(command)))
+}
+
+// Fails unless the whole log parses, so nothing after a bad line is silently dropped.
+fn commands(input: &str) -> IResult<&str, Vec<Operation<'_>>> {
+    let (input, cmds) = all_consuming(terminated(
+        separated_list1(line_ending, alt((ls, cd, unknown))),
+        multispace0,
+    ))
+    .parse(input)?;
 
     Ok((input, cmds))
 }
 
-fn calculate_sizes<'a>(
-    (mut context, mut sizes): (Vec<&'a str>, BTreeMap<Vec<&'a str>, u32>),
-    command: &'a Operation,
-) -> (Vec<&'a str>, BTreeMap<Vec<&'a str>, u32>) {
-    match command {
-        Operation::Cd(Cd::Root) => {
-            context.push("/");
-        }
-        Operation::Cd(Cd::Up) => {
-            context.pop();
-        }
-        Operation::Cd(Cd::Down(name)) => {
-            context.push(name);
-        }
-        Operation::Ls(files) => {
-            let sum = files
-                .iter()
-                .filter_map(|file| {
-                    if let Files::File { size, .. } = file {
-                        Some(size)
-                    } else {
-                        None
-                    }
-                })
-                .sum::<u32>();
-
-            for i in 0..context.len() {
-                sizes
-                    .entry(context[0..=i].to_vec())
-                    .and_modify(|v| *v += sum)
-                    .or_insert(sum);
+/// Ways to free enough space for an update, on a disk of `capacity` that needs `required` free.
+/// Smallest always means the least space freed, not the fewest directories.
+#[derive(Debug, PartialEq)]
+struct CleanupPlan {
+    need_to_free: u64,
+    // Smallest directory that frees enough space on its own, with its size.
+    single: Option<(DirId, u64)>,
+    // Smallest set of non-nested directories that together free enough space, with their
+    // combined size. Only exact when `block_size` is 1, see `plan_combined_cleanup`.
+    combined: Option<(Vec<DirId>, u64)>,
+    // Bytes per block the combined plan was searched in.
+    block_size: u64,
+}
+
+// Upper bound on the bits of planning state, one per block total per directory (64 MiB).
+const MAX_PLAN_BITS: u64 = 1 << 29;
+
+// The smallest block size that keeps the planning state for `positions` rows within
+// `MAX_PLAN_BITS`, while still tracking at least 64 blocks.
+fn plan_block_size(positions: usize, need_to_free: u64) -> u64 {
+    let max_blocks = (MAX_PLAN_BITS / 64 / positions as u64).max(1) * 64;
+
+    need_to_free.div_ceil(max_blocks).max(1)
+}
+
+fn plan_cleanup(fs: &FileSystem, capacity: u64, required: u64) -> CleanupPlan {
+    let sizes = fs.sizes();
+    let free_space = capacity.saturating_sub(sizes[ROOT]);
+    let need_to_free = required.saturating_sub(free_space);
+
+    let single = fs
+        .walk()
+        .map(|id| (id, sizes[id]))
+        .filter(|(_, size)| *size >= need_to_free)
+        .min_by_key(|(_, size)| *size);
+
+    // Every directory gets a row of planning state, plus one for the end.
+    let block_size = plan_block_size(fs.dirs.len() + 1, need_to_free);
+    let combined = plan_combined_cleanup(fs, &sizes, need_to_free, block_size)
+        .map(|dirs| {
+            let size = dirs.iter().map(|id| sizes[*id]).sum();
+            (dirs, size)
+        })
+        // Blocks are rounded, so the combined plan can come out slightly worse than the single
+        // directory.
+        .map(|(dirs, size)| match single {
+            Some((id, single_size)) if single_size < size => (vec![id], single_size),
+            _ => (dirs, size),
+        });
+
+    CleanupPlan {
+        need_to_free,
+        single,
+        combined,
+        block_size,
+    }
+}
+
+/// Finds non-nested directories that free at least `need_to_free`. This is a knapsack over
+/// directories in depth-first order, where taking a directory skips its subtree. Sizes are
+/// counted in whole blocks of `block_size` bytes, rounded down so the plan always frees enough.
+/// One-byte blocks give the least possible surplus. Larger blocks, which `plan_block_size`
+/// picks once one-byte blocks would take more than `MAX_PLAN_BITS` of state, make the plan
+/// approximate, with up to a block of extra surplus per directory taken.
+fn plan_combined_cleanup(
+    fs: &FileSystem,
+    sizes: &[u64],
+    need_to_free: u64,
+    block_size: u64,
+) -> Option<Vec<DirId>> {
+    if need_to_free == 0 {
+        return Some(vec![]);
+    }
+
+    let order = fs.walk().collect::<Vec<DirId>>();
+    let target = need_to_free.div_ceil(block_size) as usize;
+
+    let blocks = order
+        .iter()
+        .map(|id| (sizes[*id] / block_size) as usize)
+        .collect::<Vec<usize>>();
+
+    // Position just past each directory's subtree.
+    let mut subtree_len = vec![1; fs.dirs.len()];
+    for &id in order.iter().rev() {
+        if let Some(parent) = fs.parent(id) {
+            subtree_len[parent] += subtree_len[id];
+        }
+    }
+    let ends = order
+        .iter()
+        .enumerate()
+        .map(|(i, id)| i + subtree_len[*id])
+        .collect::<Vec<usize>>();
+
+    // `reachable[i]` holds the block totals below `target` that can be freed using only the
+    // directories before position `i`.
+    let words = target.div_ceil(64);
+    let mut reachable = vec![vec![0u64; words]; order.len() + 1];
+    reachable[0][0] = 1;
+
+    // Smallest total reaching the target, with the position and total taken just before it.
+    let mut best: Option<(usize, usize, usize)> = None;
+
+    for i in 0..order.len() {
+        // Rows only ever feed later rows, so split rather than copy the current one.
+        let (done, later) = reachable.split_at_mut(i + 1);
+        let current = &done[i];
+
+        if let Some(total) = first_bit_from(current, target.saturating_sub(blocks[i])) {
+            if best.is_none_or(|(best_total, _, _)| total + blocks[i] < best_total) {
+                best = Some((total + blocks[i], i, total));
             }
         }
-    };
-    (context, sizes)
+
+        shift_or(&mut later[ends[i] - i - 1], current, blocks[i]);
+        shift_or(&mut later[0], current, 0);
+    }
+
+    let (_, mut position, mut total) = best?;
+    let mut dirs = vec![order[position]];
+
+    // Walk back through the positions, preferring to skip a directory over taking one.
+    while position > 0 {
+        if has_bit(&reachable[position - 1], total) {
+            position -= 1;
+            continue;
+        }
+
+        let taken = (0..position)
+            .find(|&i| {
+                ends[i] == position
+                    && total >= blocks[i]
+                    && has_bit(&reachable[i], total - blocks[i])
+            })
+            .unwrap();
+
+        dirs.push(order[taken]);
+        total -= blocks[taken];
+        position = taken;
+    }
+
+    dirs.reverse();
+
+    Some(dirs)
+}
+
+fn has_bit(bits: &[u64], i: usize) -> bool {
+    bits.get(i / 64)
+        .is_some_and(|word| word & (1 << (i % 64)) != 0)
+}
+
+fn first_bit_from(bits: &[u64], from: usize) -> Option<usize> {
+    (from / 64..bits.len()).find_map(|w| {
+        let word = if w == from / 64 {
+            bits[w] & (u64::MAX << (from % 64))
+        } else {
+            bits[w]
+        };
+
+        (word != 0).then(|| w * 64 + word.trailing_zeros() as usize)
+    })
+}
+
+/// Sets every bit of `dst` at `i + shift` where bit `i` is set in `src`, dropping bits that
+/// shift past the end.
+fn shift_or(dst: &mut [u64], src: &[u64], shift: usize) {
+    let (word_shift, bit_shift) = (shift / 64, shift % 64);
+
+    for w in (word_shift..dst.len()).rev() {
+        let mut word = src[w - word_shift] << bit_shift;
+
+        if bit_shift > 0 && w > word_shift {
+            word |= src[w - word_shift - 1] >> (64 - bit_shift);
+        }
+
+        dst[w] |= word;
+    }
+}
+
+/// Formats a size like `du -h`, in powers of 1024 rounded up, with one decimal place below 10.
+fn human_size(size: u64) -> String {
+    let mut value = size as f64;
+
+    for unit in ["", "K", "M", "G", "T", "P"] {
+        if value < 1024.0 || unit == "P" {
+            return if unit.is_empty() {
+                size.to_string()
+            } else if value < 10.0 {
+                format!("{:.1}{unit}", (value * 10.0).ceil() / 10.0)
+            } else {
+                format!("{:.0}{unit}", value.ceil())
+            };
+        }
+
+        value /= 1024.0;
+    }
+
+    unreachable!()
+}
+
+/// Renders the file system like `tree`, with the size of every file and directory.
+fn render_tree(fs: &FileSystem) -> String {
+    fn render_dir(
+        fs: &FileSystem,
+        sizes: &[u64],
+        id: DirId,
+        prefix: &str,
+        lines: &mut Vec<String>,
+    ) {
+        let dir = fs.dir(id);
+        let mut entries = dir
+            .dirs
+            .iter()
+            .map(|(name, child)| (*name, sizes[*child], Some(*child)))
+            .chain(dir.files.iter().map(|(name, size)| (*name, *size, None)))
+            .collect::<Vec<(&str, u64, Option<DirId>)>>();
+
+        entries.sort_by_key(|(name, _, _)| *name);
+
+        for (i, (name, size, child)) in entries.iter().enumerate() {
+            let last = i == entries.len() - 1;
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            lines.push(format!("{prefix}{branch}{name} ({size})"));
+
+            if let Some(child) = child {
+                render_dir(fs, sizes, *child, &format!("{prefix}{indent}"), lines);
+            }
+        }
+    }
+
+    let sizes = fs.sizes();
+    let mut lines = vec![format!("/ ({})", sizes[ROOT])];
+
+    render_dir(fs, &sizes, ROOT, "", &mut lines);
+
+    lines.join("\n")
+}
+
+/// Every directory's total size and path, largest first, like `du | sort -rn`.
+fn disk_usage(fs: &FileSystem) -> Vec<(u64, String)> {
+    let sizes = fs.sizes();
+    let mut usage = fs
+        .walk()
+        .map(|id| (sizes[id], fs.path(id)))
+        .collect::<Vec<(u64, String)>>();
+
+    usage.sort_by(|(size_a, path_a), (size_b, path_b)| {
+        size_b.cmp(size_a).then_with(|| path_a.cmp(path_b))
+    });
+
+    usage
 }
 
 pub fn part1(input: &str) -> usize {
     let cmds = commands(input).unwrap().1;
-    let (_, sizes) = cmds.iter().fold((vec![], BTreeMap::new()), calculate_sizes);
+    let sizes = FileSystem::from_operations(&cmds).sizes();
 
-    sizes
-        .iter()
-        .filter(|(_, &size)| size < 100000)
-        .map(|(_, size)| size)
-        .sum::<u32>() as usize
+    sizes.iter().filter(|&&size| size < 100000).sum::<u64>() as usize
 }
 
 pub fn part2(input: &str) -> usize {
     let cmds = commands(input).unwrap().1;
-    let (_, sizes) = cmds.iter().fold((vec![], BTreeMap::new()), calculate_sizes);
+    let fs = FileSystem::from_operations(&cmds);
+    let plan = plan_cleanup(&fs, 70_000_000, 30_000_000);
 
-    let used_space = sizes.get(&vec!["/"]).unwrap();
-
-    let current_free_space = 70_000_000 - used_space;
-    let need_to_free_at_least = 30_000_000 - current_free_space;
-
-    let valid_dirs = sizes
-        .iter()
-        .filter(|(_, &size)| size > need_to_free_at_least)
-        .map(|(_, size)| size)
-        .collect::<Vec<&u32>>();
+    let (_, size) = plan.single.unwrap();
 
-    **valid_dirs.iter().min().unwrap() as usize
+    size as usize
 }
 
 #[cfg(test)]
@@ -183,4 +693,225 @@ $ ls
     fn part2_works() {
         assert_eq!(part2(INPUT), 24933642);
     }
+
+    #[test]
+    fn file_system_works() {
+        let cmds = commands(INPUT).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+        let e = fs.lookup("/a/e").unwrap();
+
+        assert_eq!(fs.dir(e).name, "e");
+        assert_eq!(fs.dir(e).files.get("i"), Some(&584));
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.parent(e), fs.lookup("/a"));
+        assert_eq!(fs.lookup("/a/e/../../d"), fs.lookup("/d"));
+        assert_eq!(fs.lookup("/x"), None);
+        assert_eq!(
+            fs.walk().map(|id| fs.path(id)).collect::<Vec<String>>(),
+            vec!["/", "/a", "/a/e", "/d"]
+        );
+        assert_eq!(fs.sizes()[e], 584);
+        assert_eq!(fs.sizes()[ROOT], 48381165);
+    }
+
+    #[test]
+    fn repeated_ls_is_not_double_counted() {
+        let input = format!("{INPUT}\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d");
+        let cmds = commands(&input).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+
+        assert_eq!(fs.sizes()[ROOT], 48381165);
+    }
+
+    #[test]
+    fn render_tree_works() {
+        let cmds = commands(INPUT).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+
+        assert_eq!(
+            render_tree(&fs),
+            "/ (48381165)
+├── a (94853)
+│   ├── e (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)"
+        );
+    }
+
+    #[test]
+    fn disk_usage_works() {
+        let cmds = commands(INPUT).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+
+        assert_eq!(
+            disk_usage(&fs),
+            vec![
+                (48381165, "/".to_string()),
+                (24933642, "/d".to_string()),
+                (94853, "/a".to_string()),
+                (584, "/a/e".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn human_size_works() {
+        assert_eq!(human_size(584), "584");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(94853), "93K");
+        assert_eq!(human_size(8504156), "8.2M");
+        assert_eq!(human_size(48381165), "47M");
+    }
+
+    const SHELL_INPUT: &str = "$ cd /
+$ ls
+dir My-Dir_2
+dir empty
+100 README.md
+2048 photo 01.JPG
+$ cd /My-Dir_2/sub dir
+$ ls
+10 a-b_c.TXT
+$ pwd
+/My-Dir_2/sub dir
+$ cd ../../empty
+$ ls
+$ cd /
+$ ls
+dir My-Dir_2
+dir empty
+100 README.md
+2048 photo 01.JPG";
+
+    #[test]
+    fn shell_transcripts_work() {
+        let cmds = commands(SHELL_INPUT).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+        let sizes = fs.sizes();
+
+        assert!(matches!(cmds[4], Operation::Unknown("pwd")));
+        assert_eq!(sizes[ROOT], 2158);
+        assert_eq!(sizes[fs.lookup("/My-Dir_2/sub dir").unwrap()], 10);
+        assert_eq!(sizes[fs.lookup("/empty").unwrap()], 0);
+        assert_eq!(
+            fs.walk().map(|id| fs.path(id)).collect::<Vec<String>>(),
+            vec!["/", "/My-Dir_2", "/My-Dir_2/sub dir", "/empty"]
+        );
+
+        let crlf_input = SHELL_INPUT.replace('\n', "\r\n");
+        let cmds = commands(&crlf_input).unwrap().1;
+
+        assert_eq!(FileSystem::from_operations(&cmds).sizes(), sizes);
+    }
+
+    #[test]
+    fn unknown_ls_output_is_skipped() {
+        let input = "$ cd /
+$ ls
+total 12
+100 a
+dir x
+$ cd x
+$ ls
+-rw-r--r-- 1 me me 7 b
+50 c
+";
+        let cmds = commands(input).unwrap().1;
+
+        assert!(
+            matches!(&cmds[1], Operation::Ls(files) if matches!(files[0], Files::Unknown("total 12")))
+        );
+        assert_eq!(FileSystem::from_operations(&cmds).sizes(), vec![150, 50]);
+        assert!(commands("$ cd /\n$\n$ ls").is_err());
+    }
+
+    #[test]
+    fn cd_dot_stays_put() {
+        let cmds = commands("$ cd a\n$ cd .\n$ cd ./b\n$ ls\n10 f").unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+
+        assert_eq!(
+            fs.walk().map(|id| fs.path(id)).collect::<Vec<String>>(),
+            vec!["/", "/a", "/a/b"]
+        );
+    }
+
+    const CLEANUP_INPUT: &str = "$ cd /
+$ ls
+dir a
+dir b
+dir c
+$ cd a
+$ ls
+400 x
+dir d
+$ cd d
+$ ls
+300 y
+$ cd /b
+$ ls
+500 z
+$ cd /c
+$ ls
+250 w";
+
+    #[test]
+    fn plan_cleanup_works() {
+        let cmds = commands(INPUT).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+        let d = fs.lookup("/d").unwrap();
+
+        assert_eq!(
+            plan_cleanup(&fs, 70_000_000, 30_000_000),
+            CleanupPlan {
+                need_to_free: 8381165,
+                single: Some((d, 24933642)),
+                combined: Some((vec![d], 24933642)),
+                block_size: 1,
+            }
+        );
+
+        let cmds = commands(CLEANUP_INPUT).unwrap().1;
+        let fs = FileSystem::from_operations(&cmds);
+        let plan = plan_cleanup(&fs, 2000, 1100);
+        let (dirs, size) = plan.combined.unwrap();
+
+        assert_eq!(plan.need_to_free, 550);
+        assert_eq!(plan.block_size, 1);
+        assert_eq!(plan.single, Some((fs.lookup("/a").unwrap(), 700)));
+        assert_eq!(
+            dirs.iter().map(|id| fs.path(*id)).collect::<Vec<String>>(),
+            vec!["/a/d", "/c"]
+        );
+        assert_eq!(size, 550);
+
+        let plan = plan_cleanup(&fs, 2000, 2000);
+
+        assert_eq!(plan.need_to_free, 1450);
+        assert_eq!(plan.combined, Some((vec![ROOT], 1450)));
+        assert_eq!(plan_cleanup(&fs, 2000, 3000).combined, None);
+        assert_eq!(plan_cleanup(&fs, 2000, 500).combined, Some((vec![], 0)));
+    }
+
+    #[test]
+    fn plan_state_is_bounded() {
+        assert_eq!(plan_block_size(10, 1000), 1);
+        assert_eq!(plan_block_size(189, 30_000_000), 11);
+
+        for (positions, need_to_free) in [(10_001, 30_000_000), (1_000_000, u64::MAX / 2)] {
+            let block_size = plan_block_size(positions, need_to_free);
+            let bits = need_to_free.div_ceil(block_size).div_ceil(64) * 64 * positions as u64;
+
+            assert!(bits <= MAX_PLAN_BITS.max(64 * positions as u64));
+        }
+    }
 }
@@ -1,162 +1,523 @@
-use aoc::read_file_input;
+use std::{cmp::Reverse, collections::BTreeSet, env, fmt};
+
+use aoc::{arg_value, read_file_input};
 
 use nom::{
     character::complete::{anychar, newline},
-    combinator::verify,
+    combinator::{all_consuming, opt, verify},
     multi::{many1, separated_list1},
+    sequence::terminated,
     *,
 };
 
+#[derive(Debug, PartialEq)]
+enum ForestError {
+    Unparsable {
+        line: usize,
+    },
+    RaggedRow {
+        line: usize,
+        len: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for ForestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForestError::Unparsable { line } => {
+                write!(f, "line {line} should be a row of tree heights")
+            }
+            ForestError::RaggedRow {
+                line,
+                len,
+                expected,
+            } => write!(f, "line {line} has {len} trees, expected {expected}"),
+        }
+    }
+}
+
+/// A rectangular grid of tree heights, indexed by row then column.
+#[derive(Debug, PartialEq)]
+struct Forest {
+    rows: Vec<Vec<u32>>,
+}
+
+impl Forest {
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    fn get(&self, row: usize, col: usize) -> u32 {
+        self.rows[row][col]
+    }
+}
+
+impl TryFrom<Vec<Vec<u32>>> for Forest {
+    type Error = ForestError;
+
+    fn try_from(rows: Vec<Vec<u32>>) -> Result<Self, Self::Error> {
+        let expected = rows.first().map_or(0, Vec::len);
+
+        if let Some((i, row)) = rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.len() != expected)
+        {
+            return Err(ForestError::RaggedRow {
+                line: i + 1,
+                len: row.len(),
+                expected,
+            });
+        }
+
+        Ok(Forest { rows })
+    }
+}
+
+/// The edges of the forest a tree can be seen from, one bit per direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Directions(u8);
+
+impl Directions {
+    const NORTH: Directions = Directions(1);
+    const EAST: Directions = Directions(1 << 1);
+    const SOUTH: Directions = Directions(1 << 2);
+    const WEST: Directions = Directions(1 << 3);
+
+    fn insert(&mut self, other: Directions) {
+        self.0 |= other.0;
+    }
+
+    fn contains(&self, other: Directions) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl fmt::Display for Directions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let compass = [
+            (Directions::NORTH, 'N'),
+            (Directions::EAST, 'E'),
+            (Directions::SOUTH, 'S'),
+            (Directions::WEST, 'W'),
+        ];
+
+        for (direction, label) in compass {
+            write!(f, "{}", if self.contains(direction) { label } else { '.' })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How many trees can be seen from a tree in each direction, up to the first tree at least as
+/// tall or the edge of the forest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ViewingDistances {
+    north: usize,
+    east: usize,
+    south: usize,
+    west: usize,
+}
+
+impl ViewingDistances {
+    fn scenic_score(&self) -> usize {
+        self.north * self.east * self.south * self.west
+    }
+}
+
+/// Someone looking over the forest with their eyes at `eye` height, from a cell that may be
+/// outside the forest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Observer {
+    row: isize,
+    col: isize,
+    eye: u32,
+}
+
+/// A direction to look in, as the rows and columns moved per step in lowest terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Slope {
+    rows: isize,
+    cols: isize,
+}
+
+impl Slope {
+    const COMPASS: [Slope; 8] = [
+        Slope { rows: -1, cols: 0 },
+        Slope { rows: -1, cols: 1 },
+        Slope { rows: 0, cols: 1 },
+        Slope { rows: 1, cols: 1 },
+        Slope { rows: 1, cols: 0 },
+        Slope { rows: 1, cols: -1 },
+        Slope { rows: 0, cols: -1 },
+        Slope { rows: -1, cols: -1 },
+    ];
+
+    fn new(rows: isize, cols: isize) -> Slope {
+        assert!(rows != 0 || cols != 0, "Slope should have a direction");
+
+        let divisor = gcd(rows.unsigned_abs(), cols.unsigned_abs()) as isize;
+
+        Slope {
+            rows: rows / divisor,
+            cols: cols / divisor,
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
 fn main() {
     let input = read_file_input("08.txt".to_string());
 
+    let forest = match forest(&input) {
+        Ok(forest) => forest,
+        Err(error) => {
+            eprintln!("  Invalid forest: {error}");
+            return;
+        }
+    };
+
+    if env::args().any(|arg| arg == "--visibility") {
+        println!("{}", render_visibility(&visibility(&forest)));
+    }
+
+    if let Some(k) = arg_value("--top").map(|k| k.parse().expect("Top should be a number")) {
+        for ((row, col), score) in top_scenic_trees(&forest, k) {
+            println!("  Row {row}, column {col}: {score}");
+        }
+    }
+
+    if let Some(observer) = arg_value("--look") {
+        let [row, col, eye] = observer.split(',').collect::<Vec<&str>>()[..] else {
+            panic!("Observer should be ROW,COL,EYE");
+        };
+        let observer = Observer {
+            row: row.parse().expect("Row should be a number"),
+            col: col.parse().expect("Column should be a number"),
+            eye: eye
+                .parse()
+                .expect("Eye height should be a non-negative number"),
+        };
+        let visible = if env::args().any(|arg| arg == "--compass") {
+            viewshed(&forest, observer, &Slope::COMPASS)
+        } else {
+            visible_trees(&forest, observer)
+        };
+
+        println!("{}", render_viewshed(&forest, observer, &visible));
+        println!("  Visible trees: {}", visible.len());
+    }
+
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
 }
 
 fn tree(input: &str) -> IResult<&str, u32> {
-    let (input, tree) = verify(anychar, |tree| tree.is_ascii_digit())(input)?;
+    let (input, tree) = verify(anychar, |tree| tree.is_ascii_digit()).parse(input)?;
 
     Ok((input, tree.to_digit(10).unwrap()))
 }
 
 fn parse_trees(input: &str) -> IResult<&str, Vec<Vec<u32>>> {
-    let (input, vecs) = separated_list1(newline, many1(tree))(input)?;
+    let (input, vecs) = all_consuming(terminated(
+        separated_list1(newline, many1(tree)),
+        opt(newline),
+    ))
+    .parse(input)?;
 
     Ok((input, vecs))
 }
 
-pub fn part1(input: &str) -> usize {
-    let (_, trees) = parse_trees(input).unwrap();
+fn forest(input: &str) -> Result<Forest, ForestError> {
+    let rest = match parse_trees(input) {
+        Ok((_, trees)) => return Forest::try_from(trees),
+        Err(nom::Err::Error(error) | nom::Err::Failure(error)) => error.input,
+        Err(nom::Err::Incomplete(_)) => unreachable!("complete parsers don't ask for more input"),
+    };
 
-    let max_length = trees.len() - 1;
-    let mut visible_trees: Vec<Vec<bool>> = trees
-        .iter()
-        .enumerate()
-        .map(|(i, tree_line)| {
-            let tree_line_max_length = tree_line.len() - 1;
+    // Parsing stops either inside the bad line or on the newline before it.
+    let mut offset = input.len() - rest.len();
+    if rest.starts_with('\n') && !input[..offset].ends_with('\n') {
+        offset += 1;
+    }
 
-            tree_line
-                .iter()
-                .enumerate()
-                .map(|(j, _)| (i == 0 || i == max_length || j == 0 || j == tree_line_max_length))
-                .collect()
-        })
-        .collect();
+    Err(ForestError::Unparsable {
+        line: input[..offset].matches('\n').count() + 1,
+    })
+}
 
-    for i in 0..trees.len() {
-        let mut current_tree_size = 0;
+// Marks every tree taller than all the trees before it along `cells`.
+fn sweep(
+    forest: &Forest,
+    visible: &mut [Vec<Directions>],
+    cells: impl Iterator<Item = (usize, usize)>,
+    direction: Directions,
+) {
+    let mut tallest = None;
 
-        for j in 0..trees[0].len() {
-            if j == 0 {
-                current_tree_size = trees[i][j] as usize;
-            } else if trees[i][j] > current_tree_size as u32 {
-                current_tree_size = trees[i][j] as usize;
-                visible_trees[i][j] = true;
-            }
+    for (row, col) in cells {
+        let tree = forest.get(row, col);
+
+        if tallest.is_none_or(|tallest| tree > tallest) {
+            visible[row][col].insert(direction);
+            tallest = Some(tree);
         }
     }
+}
 
-    for i in (0..trees.len()).rev() {
-        let mut current_tree_size = 0;
+/// For every tree, the edges of the forest it is visible from.
+fn visibility(forest: &Forest) -> Vec<Vec<Directions>> {
+    let (height, width) = (forest.height(), forest.width());
+    let mut visible = vec![vec![Directions::default(); width]; height];
 
-        for j in (0..trees[0].len()).rev() {
-            if j == trees.len() - 1 {
-                current_tree_size = trees[i][j] as usize;
-            } else if trees[i][j] > current_tree_size as u32 {
-                current_tree_size = trees[i][j] as usize;
-                visible_trees[i][j] = true;
-            }
-        }
+    for row in 0..height {
+        sweep(
+            forest,
+            &mut visible,
+            (0..width).map(|col| (row, col)),
+            Directions::WEST,
+        );
+        sweep(
+            forest,
+            &mut visible,
+            (0..width).rev().map(|col| (row, col)),
+            Directions::EAST,
+        );
     }
 
-    for i in 0..trees.len() {
-        let mut current_tree_size = 0;
+    for col in 0..width {
+        sweep(
+            forest,
+            &mut visible,
+            (0..height).map(|row| (row, col)),
+            Directions::NORTH,
+        );
+        sweep(
+            forest,
+            &mut visible,
+            (0..height).rev().map(|row| (row, col)),
+            Directions::SOUTH,
+        );
+    }
+
+    visible
+}
 
-        for j in 0..trees[0].len() {
-            if j == 0 {
-                current_tree_size = trees[j][i] as usize;
-            } else if trees[j][i] > current_tree_size as u32 {
-                current_tree_size = trees[j][i] as usize;
-                visible_trees[j][i] = true;
+/// Renders the visibility of each tree as the compass points it can be seen from, with `.`
+/// for the ones it can't, e.g. `N..W` for a tree visible from the north and west edges.
+fn render_visibility(visible: &[Vec<Directions>]) -> String {
+    visible
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|directions| directions.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Viewing distances looking back along `heights` from each tree, in a single pass. The stack
+/// holds the positions of trees not yet blocked by a later tree at least as tall, so its top
+/// is the nearest tree that blocks the view.
+fn viewing_distances_along(heights: impl Iterator<Item = u32>) -> Vec<usize> {
+    let mut stack: Vec<(usize, u32)> = vec![];
+
+    heights
+        .enumerate()
+        .map(|(position, height)| {
+            while stack.last().is_some_and(|&(_, blocker)| blocker < height) {
+                stack.pop();
             }
+
+            let distance = stack
+                .last()
+                .map_or(position, |&(blocker, _)| position - blocker);
+
+            stack.push((position, height));
+
+            distance
+        })
+        .collect()
+}
+
+fn viewing_distances(forest: &Forest) -> Vec<Vec<ViewingDistances>> {
+    let (height, width) = (forest.height(), forest.width());
+    let mut distances = vec![vec![ViewingDistances::default(); width]; height];
+
+    for (row, cells) in distances.iter_mut().enumerate() {
+        let west = viewing_distances_along((0..width).map(|col| forest.get(row, col)));
+        let east = viewing_distances_along((0..width).rev().map(|col| forest.get(row, col)));
+
+        for (col, cell) in cells.iter_mut().enumerate() {
+            cell.west = west[col];
+            cell.east = east[width - 1 - col];
         }
     }
 
-    for i in (0..trees.len()).rev() {
-        let mut current_tree_size = 0;
+    for col in 0..width {
+        let north = viewing_distances_along((0..height).map(|row| forest.get(row, col)));
+        let south = viewing_distances_along((0..height).rev().map(|row| forest.get(row, col)));
 
-        for j in (0..trees[0].len()).rev() {
-            if j == trees.len() - 1 {
-                current_tree_size = trees[j][i] as usize;
-            } else if trees[j][i] > current_tree_size as u32 {
-                current_tree_size = trees[j][i] as usize;
-                visible_trees[j][i] = true;
-            }
+        for (row, cells) in distances.iter_mut().enumerate() {
+            cells[col].north = north[row];
+            cells[col].south = south[height - 1 - row];
         }
     }
 
-    visible_trees.iter().flatten().filter(|&&v| v).count()
+    distances
 }
 
-pub fn part2(input: &str) -> usize {
-    let (_, trees) = parse_trees(input).unwrap();
-    let mut high_scenic_score = 0;
-
-    let i_max = trees.len();
-    let j_max = trees[0].len();
-
-    for (i, tree_line) in trees.iter().enumerate() {
-        for (j, treehouse_height) in tree_line.iter().enumerate() {
-            let mut scores = [0, 0, 0, 0];
-
-            // to left
-            for j_position in (0..j).rev() {
-                if trees[i][j_position] < *treehouse_height {
-                    scores[0] += 1;
-                } else {
-                    scores[0] += 1;
-                    break;
-                }
-            }
-            // to right
-            for j_position in (j + 1)..j_max {
-                if trees[i][j_position] < *treehouse_height {
-                    scores[1] += 1;
-                } else {
-                    scores[1] += 1;
-                    break;
-                }
-            }
+fn scenic_scores(forest: &Forest) -> Vec<Vec<usize>> {
+    viewing_distances(forest)
+        .iter()
+        .map(|row| row.iter().map(ViewingDistances::scenic_score).collect())
+        .collect()
+}
 
-            // to up
-            for i_position in (0..i).rev() {
-                if trees[i_position][j] < *treehouse_height {
-                    scores[2] += 1;
-                } else {
-                    scores[2] += 1;
-                    break;
-                }
-            }
-            // to down
-            #[allow(clippy::needless_range_loop)]
-            for i_position in (i + 1)..i_max {
-                if trees[i_position][j] < *treehouse_height {
-                    scores[3] += 1;
-                } else {
-                    scores[3] += 1;
-                    break;
-                }
-            }
-            let scenic_score: u32 = scores.iter().product();
+/// The `k` trees with the highest scenic scores as `((row, col), score)`, best first, with ties
+/// in reading order.
+fn top_scenic_trees(forest: &Forest, k: usize) -> Vec<((usize, usize), usize)> {
+    let mut trees = scenic_scores(forest)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(row, scores)| {
+            scores
+                .into_iter()
+                .enumerate()
+                .map(move |(col, score)| ((row, col), score))
+        })
+        .collect::<Vec<((usize, usize), usize)>>();
 
-            if scenic_score > high_scenic_score {
-                high_scenic_score = scenic_score
-            }
+    trees.sort_by_key(|&(position, score)| (Reverse(score), position));
+    trees.truncate(k);
+
+    trees
+}
+
+// The steps `k >= 1` for which `start + k * delta` falls within `0..len`, as an inclusive
+// range that is empty when it never does.
+fn steps_inside(start: isize, delta: isize, len: usize) -> (isize, isize) {
+    let last = len as isize - 1;
+
+    match delta {
+        0 if (0..=last).contains(&start) => (1, isize::MAX),
+        0 => (1, 0),
+        _ if delta < 0 => steps_inside(last - start, -delta, len),
+        _ => (
+            1.max(-start.div_euclid(delta)),
+            (last - start).div_euclid(delta),
+        ),
+    }
+}
+
+/// The trees an observer can see looking along `slope`, nearest first. Only trees centred on
+/// the line count. A tree is visible when its top rises above the sight line over every
+/// nearer tree, so a tall tree hides shorter ones behind it but not taller ones, and an
+/// observer above the canopy sees further than one below it.
+fn line_of_sight(forest: &Forest, observer: Observer, slope: Slope) -> Vec<(usize, usize)> {
+    let (first_row, last_row) = steps_inside(observer.row, slope.rows, forest.height());
+    let (first_col, last_col) = steps_inside(observer.col, slope.cols, forest.width());
+
+    let mut visible = vec![];
+    // The steepest sight line so far, as a rise over a number of steps.
+    let mut horizon: Option<(i64, i64)> = None;
+
+    for step in first_row.max(first_col)..=last_row.min(last_col) {
+        let row = (observer.row + step * slope.rows) as usize;
+        let col = (observer.col + step * slope.cols) as usize;
+        let rise = forest.get(row, col) as i64 - observer.eye as i64;
+        let run = step as i64;
+
+        if horizon.is_none_or(|(horizon_rise, horizon_run)| rise * horizon_run > horizon_rise * run)
+        {
+            visible.push((row, col));
+            horizon = Some((rise, run));
         }
     }
 
-    high_scenic_score as usize
+    visible
+}
+
+/// Every tree the observer can see looking along any of `slopes`.
+fn viewshed(forest: &Forest, observer: Observer, slopes: &[Slope]) -> BTreeSet<(usize, usize)> {
+    slopes
+        .iter()
+        .flat_map(|slope| line_of_sight(forest, observer, *slope))
+        .collect()
+}
+
+/// Every tree the observer can see looking in any direction, which means along the slope
+/// towards each tree in the forest.
+fn visible_trees(forest: &Forest, observer: Observer) -> BTreeSet<(usize, usize)> {
+    let slopes = (0..forest.height() as isize)
+        .flat_map(|row| (0..forest.width() as isize).map(move |col| (row, col)))
+        .filter(|&(row, col)| (row, col) != (observer.row, observer.col))
+        .map(|(row, col)| Slope::new(row - observer.row, col - observer.col))
+        .collect::<BTreeSet<Slope>>()
+        .into_iter()
+        .collect::<Vec<Slope>>();
+
+    viewshed(forest, observer, &slopes)
+}
+
+/// Renders the forest with visible trees as their heights, hidden trees as `.` and the
+/// observer as `@` when they are inside the forest.
+fn render_viewshed(
+    forest: &Forest,
+    observer: Observer,
+    visible: &BTreeSet<(usize, usize)>,
+) -> String {
+    (0..forest.height())
+        .map(|row| {
+            (0..forest.width())
+                .map(|col| {
+                    if (row as isize, col as isize) == (observer.row, observer.col) {
+                        '@'
+                    } else if visible.contains(&(row, col)) {
+                        char::from_digit(forest.get(row, col), 10).unwrap()
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn part1(input: &str) -> usize {
+    let forest = forest(input).unwrap();
+
+    visibility(&forest)
+        .iter()
+        .flatten()
+        .filter(|directions| !directions.is_empty())
+        .count()
+}
+
+pub fn part2(input: &str) -> usize {
+    let forest = forest(input).unwrap();
+
+    scenic_scores(&forest).into_iter().flatten().max().unwrap()
 }
 
 #[cfg(test)]
@@ -178,4 +539,141 @@ mod tests {
     fn part2_works() {
         assert_eq!(part2(INPUT), 8);
     }
+
+    #[test]
+    fn rectangular_forests_work() {
+        assert_eq!(part1("3037\n2551\n6533"), 12);
+        assert_eq!(part1("30373\n25512"), 10);
+        assert_eq!(part1("3\n2\n6"), 3);
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        assert_eq!(
+            forest("303\n25\n655"),
+            Err(ForestError::RaggedRow {
+                line: 2,
+                len: 2,
+                expected: 3
+            })
+        );
+    }
+
+    #[test]
+    fn unparsable_lines_are_rejected() {
+        assert_eq!(
+            forest("303\n\n255\n655"),
+            Err(ForestError::Unparsable { line: 2 })
+        );
+        assert_eq!(forest("x03\n255"), Err(ForestError::Unparsable { line: 1 }));
+        assert_eq!(forest("303\n25x"), Err(ForestError::Unparsable { line: 2 }));
+        assert_eq!(
+            forest("303\n255\n\n"),
+            Err(ForestError::Unparsable { line: 3 })
+        );
+        assert_eq!(forest(""), Err(ForestError::Unparsable { line: 1 }));
+        assert_eq!(forest("303\n255\n").map(|forest| forest.height()), Ok(2));
+    }
+
+    #[test]
+    fn render_visibility_works() {
+        let forest = forest(INPUT).unwrap();
+
+        assert_eq!(
+            render_visibility(&visibility(&forest)),
+            "N..W N... N... NE.W NE..
+...W N..W NE.. .... .E..
+NESW .E.. .... .E.. .E..
+...W .... ..SW .... NESW
+..SW ..SW ..S. NESW .ES."
+        );
+    }
+
+    #[test]
+    fn viewing_distances_work() {
+        let forest = forest(INPUT).unwrap();
+
+        assert_eq!(
+            viewing_distances(&forest)[3][2],
+            ViewingDistances {
+                north: 2,
+                east: 2,
+                south: 1,
+                west: 2
+            }
+        );
+        assert_eq!(
+            scenic_scores(&forest),
+            vec![
+                vec![0, 0, 0, 0, 0],
+                vec![0, 1, 4, 1, 0],
+                vec![0, 6, 1, 2, 0],
+                vec![0, 1, 8, 3, 0],
+                vec![0, 0, 0, 0, 0],
+            ]
+        );
+        assert_eq!(
+            top_scenic_trees(&forest, 3),
+            vec![((3, 2), 8), ((2, 1), 6), ((1, 2), 4)]
+        );
+    }
+
+    #[test]
+    fn line_of_sight_works() {
+        let forest = forest(INPUT).unwrap();
+        let inside = Observer {
+            row: 2,
+            col: 2,
+            eye: 3,
+        };
+        let outside = Observer {
+            row: -1,
+            col: -1,
+            eye: 0,
+        };
+
+        assert_eq!(Slope::new(4, -6), Slope { rows: 2, cols: -3 });
+        assert_eq!(
+            line_of_sight(&forest, inside, Slope::new(0, 1)),
+            vec![(2, 3)]
+        );
+        assert_eq!(
+            line_of_sight(&forest, inside, Slope::new(-1, -1)),
+            vec![(1, 1)]
+        );
+        assert_eq!(
+            line_of_sight(&forest, outside, Slope::new(1, 1)),
+            vec![(0, 0)]
+        );
+        assert_eq!(
+            line_of_sight(&forest, outside, Slope::new(2, 1)),
+            vec![(1, 0)]
+        );
+        assert_eq!(line_of_sight(&forest, outside, Slope::new(-1, 1)), vec![]);
+        assert_eq!(viewshed(&forest, inside, &Slope::COMPASS).len(), 9);
+
+        let visible = visible_trees(&forest, inside);
+
+        assert_eq!(visible.len(), 17);
+        assert_eq!(
+            render_viewshed(&forest, inside, &visible),
+            ".0.73
+25512
+.5@3.
+33549
+.5.9."
+        );
+
+        let visible = visible_trees(&forest, outside);
+
+        assert_eq!(visible.len(), 20);
+        assert_eq!(
+            render_viewshed(&forest, outside, &visible),
+            "30373
+2.512
+65.32
+3.5.9
+3539."
+        );
+    }
 }
@@ -1,200 +1,387 @@
-use aoc::read_file_input;
+use aoc::{arg_value, read_file_input};
 
-use ::lending_iterator::prelude::*;
-use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{self, newline},
+    character::complete::{self, char, line_ending, not_line_ending, space0},
+    combinator::{all_consuming, eof, peek, value},
     multi::separated_list1,
-    sequence::separated_pair,
+    sequence::{delimited, pair, preceded, separated_pair},
     *,
 };
-use std::collections::HashSet;
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Write},
+};
 
 fn main() {
     let input = read_file_input("09.txt".to_string());
 
+    let knots =
+        arg_value("--knots").map_or(10, |knots| knots.parse().expect("Knots should be a number"));
+    let knot = arg_value("--knot").map(|knot| knot.parse().expect("Knot should be a number"));
+
+    if knots == 0 {
+        eprintln!("  A rope needs at least one knot");
+        return;
+    }
+
+    if let Some(knot) = knot.filter(|knot| *knot >= knots) {
+        eprintln!(
+            "  Knot {knot} is out of range, the rope has knots 0 to {}",
+            knots - 1
+        );
+        return;
+    }
+
+    if env::args().any(|arg| arg == "--heatmap") {
+        let (_, motions) = motions(&input).unwrap();
+        let counts = visits(&motions, knots).counts(knot);
+
+        if let Some(((min_x, min_y), (max_x, max_y))) = bounding_box(counts.keys()) {
+            println!("  Bounding box: ({min_x}, {min_y}) to ({max_x}, {max_y})");
+        }
+
+        println!("{}", render_heatmap(&counts));
+    }
+
+    if env::args().any(|arg| arg == "--first-visits") {
+        let (_, motions) = motions(&input).unwrap();
+        let visits = visits(&motions, knots);
+
+        for (i, (x, y)) in visits
+            .knot(knot.unwrap_or(knots - 1))
+            .first_visits
+            .iter()
+            .enumerate()
+        {
+            println!("  {i}: ({x}, {y})");
+        }
+    }
+
+    if env::args().any(|arg| arg == "--trail") {
+        let (_, motions) = motions(&input).unwrap();
+
+        write_trail(&motions, knots, io::stdout().lock()).expect("Trail should be written");
+    }
+
     println!("  Part 1: {}", part1(&input));
     println!("  Part 2: {}", part2(&input));
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Direction {
     Left,
     Right,
     Up,
     Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    fn delta(&self) -> (i32, i32) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Motion {
+    Repeat(Direction, u32),
+    /// Walks the head to an absolute position, moving diagonally until it lines up with it.
+    MoveTo(i32, i32),
+}
+
+impl Motion {
+    /// The next step of the head, which is at `head` after `taken` steps of this motion, or
+    /// `None` once the motion is done.
+    fn next_step(&self, head: (i32, i32), taken: u32) -> Option<(i32, i32)> {
+        match *self {
+            Motion::Repeat(direction, repeat) => (taken < repeat).then(|| direction.delta()),
+            Motion::MoveTo(x, y) => {
+                (head != (x, y)).then(|| ((x - head.0).signum(), (y - head.1).signum()))
+            }
+        }
+    }
 }
 
 fn direction(input: &str) -> IResult<&str, Direction> {
     let (input, dir) = alt((
+        tag("UL").map(|_| Direction::UpLeft),
+        tag("UR").map(|_| Direction::UpRight),
+        tag("DL").map(|_| Direction::DownLeft),
+        tag("DR").map(|_| Direction::DownRight),
         complete::char('L').map(|_| Direction::Left),
         complete::char('R').map(|_| Direction::Right),
         complete::char('U').map(|_| Direction::Up),
         complete::char('D').map(|_| Direction::Down),
-    ))(input)?;
+    ))
+    .parse(input)?;
 
     Ok((input, dir))
 }
 
-fn directions(input: &str) -> IResult<&str, Vec<Direction>> {
-    let (input, motions) =
-        separated_list1(newline, separated_pair(direction, tag(" "), complete::u32))(input)?;
+fn move_to(input: &str) -> IResult<&str, Motion> {
+    let (input, (x, y)) = delimited(
+        tag("move to ("),
+        separated_pair(complete::i32, pair(char(','), space0), complete::i32),
+        char(')'),
+    )
+    .parse(input)?;
 
-    let directions = motions
-        .iter()
-        .flat_map(|(dir, repeat)| vec![*dir; *repeat as usize])
-        .collect();
+    Ok((input, Motion::MoveTo(x, y)))
+}
 
-    Ok((input, directions))
+// A motion, or `None` for blank and `#` comment lines.
+fn line(input: &str) -> IResult<&str, Option<Motion>> {
+    let (input, motion) = alt((
+        move_to.map(Some),
+        separated_pair(direction, tag(" "), complete::u32)
+            .map(|(dir, repeat)| Some(Motion::Repeat(dir, repeat))),
+        value(None, preceded(char('#'), not_line_ending)),
+        value(None, peek(alt((line_ending, eof)))),
+    ))
+    .parse(input)?;
+
+    Ok((input, motion))
 }
 
-pub fn part1(input: &str) -> usize {
-    let (_, directions) = directions(input).unwrap();
+// Fails unless every line parses, so a typo can't silently end the motions early.
+fn motions(input: &str) -> IResult<&str, Vec<Motion>> {
+    let (input, lines) = all_consuming(separated_list1(line_ending, line)).parse(input)?;
 
-    let mut head = (0, 0);
-    let mut tail = (0, 0);
-    let mut tail_positions = HashSet::from([tail]);
+    Ok((input, lines.into_iter().flatten().collect()))
+}
 
-    for head_move in directions.iter() {
-        match head_move {
-            Direction::Left => {
-                head.0 -= 1;
-            }
-            Direction::Right => {
-                head.0 += 1;
-            }
-            Direction::Up => {
-                head.1 += 1;
-            }
-            Direction::Down => {
-                head.1 -= 1;
-            }
+/// A rope of knots, where each knot follows the one in front of it and the first knot is the
+/// head.
+#[derive(Clone, Debug, PartialEq)]
+struct Rope {
+    knots: Vec<(i32, i32)>,
+}
+
+impl Rope {
+    fn new(knots: usize) -> Rope {
+        assert!(knots > 0, "Rope should have at least one knot");
+
+        Rope {
+            knots: vec![(0, 0); knots],
         }
+    }
+
+    fn knot(&self, knot: usize) -> (i32, i32) {
+        self.knots[knot]
+    }
 
-        let x_range = (head.0 - 1)..=(head.0 + 1);
-        let y_range = (head.1 - 1)..=(head.1 + 1);
+    /// Moves the head by `(dx, dy)`, then pulls each following knot along.
+    fn step(&mut self, (dx, dy): (i32, i32)) {
+        let head = &mut self.knots[0];
 
-        let tail_is_connected = x_range
-            .cartesian_product(y_range)
-            .any(|tuple| tuple == tail);
+        head.0 += dx;
+        head.1 += dy;
 
-        if !tail_is_connected {
-            let mut new_tail = head;
+        for i in 1..self.knots.len() {
+            let (leader, follower) = (self.knots[i - 1], self.knots[i]);
+            let next = follow(leader, follower);
 
-            match head_move {
-                Direction::Left => {
-                    new_tail.0 += 1;
-                }
-                Direction::Right => {
-                    new_tail.0 -= 1;
-                }
-                Direction::Up => {
-                    new_tail.1 -= 1;
-                }
-                Direction::Down => {
-                    new_tail.1 += 1;
-                }
+            // A knot that stays put leaves the rest of the rope where it is.
+            if next == follower {
+                break;
             }
 
-            tail = new_tail;
-            tail_positions.insert(new_tail);
+            self.knots[i] = next;
+        }
+    }
+
+    /// Moves the head through `motion` one step at a time, calling `on_step` after each step.
+    fn apply(&mut self, motion: Motion, mut on_step: impl FnMut(&Rope)) {
+        let mut taken = 0;
+
+        while let Some(delta) = motion.next_step(self.knot(0), taken) {
+            self.step(delta);
+            taken += 1;
+            on_step(self);
         }
     }
+}
 
-    tail_positions.len()
+/// Where `follower` ends up once `leader` has moved: it stays put while they touch (including
+/// diagonally), otherwise it takes one step towards the leader on each axis they differ in.
+fn follow(leader: (i32, i32), follower: (i32, i32)) -> (i32, i32) {
+    let (dx, dy) = (leader.0 - follower.0, leader.1 - follower.1);
+
+    if dx.abs() <= 1 && dy.abs() <= 1 {
+        follower
+    } else {
+        (follower.0 + dx.signum(), follower.1 + dy.signum())
+    }
 }
 
-pub fn part2(input: &str) -> usize {
-    let (_, directions) = directions(input).unwrap();
+/// How often one knot moved into each cell, and the order it first reached them in.
+#[derive(Debug, Default)]
+struct KnotVisits {
+    counts: HashMap<(i32, i32), usize>,
+    first_visits: Vec<(i32, i32)>,
+    position: Option<(i32, i32)>,
+}
 
-    let mut rope = [(0, 0); 10];
-    let mut tail_positions = HashSet::from([*rope.last().unwrap()]);
+impl KnotVisits {
+    // Steps where the knot stays put aren't visits.
+    fn visit(&mut self, position: (i32, i32)) {
+        if self.position == Some(position) {
+            return;
+        }
 
-    for head_move in directions.iter() {
-        match head_move {
-            Direction::Left => {
-                rope[0].0 -= 1;
-            }
-            Direction::Right => {
-                rope[0].0 += 1;
-            }
-            Direction::Up => {
-                rope[0].1 += 1;
-            }
-            Direction::Down => {
-                rope[0].1 -= 1;
-            }
+        self.position = Some(position);
+
+        let count = self.counts.entry(position).or_default();
+
+        if *count == 0 {
+            self.first_visits.push(position);
         }
 
-        let mut rope_windows = rope.windows_mut::<2>();
-
-        while let Some([ref mut head, ref mut tail]) = rope_windows.next() {
-            let x_range = (head.0 - 1)..=(head.0 + 1);
-            let y_range = (head.1 - 1)..=(head.1 + 1);
-
-            let tail_is_connected = x_range
-                .cartesian_product(y_range)
-                .any(|tuple| tuple == *tail);
-
-            if !tail_is_connected {
-                if head.0 == tail.0 {
-                    if head.1 > tail.1 {
-                        tail.1 += 1;
-                    } else {
-                        tail.1 -= 1;
-                    }
-                } else if head.1 == tail.1 {
-                    if head.0 > tail.0 {
-                        tail.0 += 1;
-                    } else {
-                        tail.0 -= 1;
-                    }
-                } else {
-                    let x_range = (head.0 - 1)..=(head.0 + 1);
-                    let y_range = (head.1 - 1)..=(head.1 + 1);
-
-                    let head_3x3 = x_range.cartesian_product(y_range).collect::<Vec<_>>();
-
-                    let x_range = (tail.0 - 1)..=(tail.0 + 1);
-                    let y_range = (tail.1 - 1)..=(tail.1 + 1);
-
-                    let maybe_new_tail: Vec<(i32, i32)> = x_range
-                        .cartesian_product(y_range)
-                        .filter(|tuple| head_3x3.contains(tuple))
-                        .collect();
-
-                    match maybe_new_tail.len() {
-                        2 => {
-                            let new_head_cross_positions = [
-                                (head.0 - 1, head.1),
-                                (head.0 + 1, head.1),
-                                (head.0, head.1 - 1),
-                                (head.0, head.1 + 1),
-                            ];
-
-                            let next = maybe_new_tail
-                                .iter()
-                                .find(|tuple| new_head_cross_positions.contains(tuple))
-                                .unwrap();
-                            *tail = *next;
-                        }
-                        1 => {
-                            *tail = maybe_new_tail[0];
-                        }
-                        _ => {
-                            panic!("unknown tail length");
-                        }
-                    };
+        *count += 1;
+    }
+}
+
+/// Visits by every knot of a rope, indexed by knot. The starting position counts as a visit.
+#[derive(Debug)]
+struct Visits {
+    knots: Vec<KnotVisits>,
+}
+
+impl Visits {
+    fn record(&mut self, rope: &Rope) {
+        for (knot, visits) in self.knots.iter_mut().enumerate() {
+            visits.visit(rope.knot(knot));
+        }
+    }
+
+    fn knot(&self, knot: usize) -> &KnotVisits {
+        &self.knots[knot]
+    }
+
+    /// Visits per cell by a single knot, or summed over every knot.
+    fn counts(&self, knot: Option<usize>) -> HashMap<(i32, i32), usize> {
+        match knot {
+            Some(knot) => self.knot(knot).counts.clone(),
+            None => {
+                let mut counts = HashMap::new();
+
+                for (position, count) in self.knots.iter().flat_map(|visits| &visits.counts) {
+                    *counts.entry(*position).or_default() += count;
                 }
+
+                counts
+            }
+        }
+    }
+}
+
+fn visits(motions: &[Motion], knots: usize) -> Visits {
+    let mut rope = Rope::new(knots);
+    let mut visits = Visits {
+        knots: (0..knots).map(|_| KnotVisits::default()).collect(),
+    };
+
+    visits.record(&rope);
+
+    for motion in motions {
+        rope.apply(*motion, |rope| visits.record(rope));
+    }
+
+    visits
+}
+
+/// The smallest and largest corners of the box around `cells`.
+fn bounding_box<'a>(
+    cells: impl IntoIterator<Item = &'a (i32, i32)>,
+) -> Option<((i32, i32), (i32, i32))> {
+    cells
+        .into_iter()
+        .fold(None, |bounds, &(x, y)| match bounds {
+            None => Some(((x, y), (x, y))),
+            Some(((min_x, min_y), (max_x, max_y))) => {
+                Some(((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y))))
             }
+        })
+}
+
+/// Renders visit counts with up at the top, as `.` for unvisited cells, the count for cells
+/// visited up to 9 times and `#` for anything busier.
+fn render_heatmap(counts: &HashMap<(i32, i32), usize>) -> String {
+    let Some(((min_x, min_y), (max_x, max_y))) = bounding_box(counts.keys()) else {
+        return String::new();
+    };
+
+    (min_y..=max_y)
+        .rev()
+        .map(|y| {
+            (min_x..=max_x)
+                .map(|x| match counts.get(&(x, y)) {
+                    None => '.',
+                    Some(&count) if count > 9 => '#',
+                    Some(&count) => char::from_digit(count as u32, 10).unwrap(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Writes the position of every knot after every step as CSV, with the starting positions as
+/// step 0.
+fn write_trail(motions: &[Motion], knots: usize, mut out: impl Write) -> io::Result<()> {
+    let mut rope = Rope::new(knots);
+    let mut step = 0;
+
+    writeln!(out, "step,knot,x,y")?;
+    write_knots(&mut out, &rope, step)?;
+
+    for motion in motions {
+        let mut taken = 0;
+
+        while let Some(delta) = motion.next_step(rope.knot(0), taken) {
+            rope.step(delta);
+            taken += 1;
+            step += 1;
+            write_knots(&mut out, &rope, step)?;
         }
+    }
 
-        tail_positions.insert(*rope.last().unwrap());
+    Ok(())
+}
+
+fn write_knots(out: &mut impl Write, rope: &Rope, step: usize) -> io::Result<()> {
+    for (knot, (x, y)) in rope.knots.iter().enumerate() {
+        writeln!(out, "{step},{knot},{x},{y}")?;
     }
 
-    tail_positions.len()
+    Ok(())
+}
+
+pub fn part1(input: &str) -> usize {
+    let (_, motions) = motions(input).unwrap();
+
+    visits(&motions, 2).knot(1).counts.len()
+}
+
+pub fn part2(input: &str) -> usize {
+    let (_, motions) = motions(input).unwrap();
+
+    visits(&motions, 10).knot(9).counts.len()
 }
 
 #[cfg(test)]
@@ -219,4 +406,142 @@ R 2";
     fn part2_works() {
         assert_eq!(part2(INPUT), 1);
     }
+
+    const LARGER_INPUT: &str = "R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20";
+
+    const EXTENDED_INPUT: &str = "# Out along the diagonal and back
+UR 3
+
+move to (0, -4)
+DL 2
+move to (5,-2)
+R 1
+";
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        assert!(motions("R 4\nX 5\nU 30").is_err());
+        assert!(motions("R 4\n  U 3").is_err());
+        assert_eq!(
+            motions("R 4\r\n\r\nU 3\r\n").unwrap().1,
+            vec![
+                Motion::Repeat(Direction::Right, 4),
+                Motion::Repeat(Direction::Up, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn follow_works() {
+        assert_eq!(follow((1, 1), (0, 0)), (0, 0));
+        assert_eq!(follow((2, 0), (0, 0)), (1, 0));
+        assert_eq!(follow((2, 1), (0, 0)), (1, 1));
+        assert_eq!(follow((2, 2), (0, 0)), (1, 1));
+    }
+
+    #[test]
+    fn rope_works() {
+        let (_, motions) = motions(LARGER_INPUT).unwrap();
+        let visits = visits(&motions, 10);
+
+        assert_eq!(visits.knot(9).counts.len(), 36);
+        assert_eq!(visits.knot(1).counts.len(), 88);
+
+        let mut rope = Rope::new(3);
+
+        for direction in [
+            Direction::Right,
+            Direction::Right,
+            Direction::Up,
+            Direction::Up,
+        ] {
+            rope.step(direction.delta());
+        }
+
+        assert_eq!(rope.knots, vec![(2, 2), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn extended_motions_work() {
+        let (rest, motions) = motions(EXTENDED_INPUT).unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            motions,
+            vec![
+                Motion::Repeat(Direction::UpRight, 3),
+                Motion::MoveTo(0, -4),
+                Motion::Repeat(Direction::DownLeft, 2),
+                Motion::MoveTo(5, -2),
+                Motion::Repeat(Direction::Right, 1),
+            ]
+        );
+
+        let mut rope = Rope::new(3);
+        let mut steps = 0;
+
+        for motion in &motions {
+            rope.apply(*motion, |_| steps += 1);
+        }
+
+        assert_eq!(steps, 3 + 7 + 2 + 7 + 1);
+        assert_eq!(rope.knots, vec![(6, -2), (5, -2), (4, -2)]);
+        assert_eq!(visits(&motions, 3).knot(2).counts.len(), 10);
+    }
+
+    #[test]
+    fn visits_work() {
+        let (_, motions) = motions(INPUT).unwrap();
+        let visits = visits(&motions, 2);
+        let tail = visits.knot(1);
+
+        assert_eq!(
+            tail.first_visits[..5],
+            [(0, 0), (1, 0), (2, 0), (3, 0), (4, 1)]
+        );
+        assert_eq!(tail.counts[&(3, 0)], 1);
+        assert_eq!(tail.counts[&(4, 3)], 2);
+        assert_eq!(bounding_box(tail.counts.keys()), Some(((0, 0), (4, 4))));
+        assert_eq!(
+            render_heatmap(&visits.counts(Some(1))),
+            "..11.
+...12
+.1111
+....1
+1111."
+        );
+        assert_eq!(visits.counts(None).values().sum::<usize>(), 39);
+    }
+
+    #[test]
+    fn write_trail_works() {
+        let mut csv = vec![];
+
+        write_trail(&[Motion::Repeat(Direction::Right, 2)], 2, &mut csv).unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "step,knot,x,y
+0,0,0,0
+0,1,0,0
+1,0,1,0
+1,1,0,0
+2,0,2,0
+2,1,1,0
+"
+        );
+
+        // A full buffer fails the write instead of being skipped over.
+        let mut short = [0; 20];
+        let trail = write_trail(&[Motion::Repeat(Direction::Right, 2)], 2, &mut short[..]);
+
+        assert_eq!(trail.unwrap_err().kind(), io::ErrorKind::WriteZero);
+    }
 }
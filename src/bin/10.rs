@@ -1,29 +1,147 @@
-use aoc::read_file_input;
+use aoc::{arg_value, read_file_input};
 
 use itertools::Itertools;
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::{self, newline},
+    character::complete::{self, char, multispace0, newline, satisfy},
+    combinator::{all_consuming, success, verify},
     multi::separated_list1,
-    sequence::preceded,
+    sequence::{preceded, terminated},
     *,
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, env, fs, iter, str::FromStr};
 
-#[derive(Debug)]
-enum Instruction {
+/// The instructions the CPU knows, without their operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Opcode {
     Noop,
-    Add(i32),
+    Add,
+    Sub,
+    Mul,
+    Set,
+    Jump,
+    JumpIfZero,
+    JumpIfNotZero,
 }
 
-use Instruction::{Add, Noop};
+impl Opcode {
+    const ALL: [(Opcode, &'static str); 8] = [
+        (Opcode::Noop, "noop"),
+        (Opcode::Add, "add"),
+        (Opcode::Sub, "sub"),
+        (Opcode::Mul, "mul"),
+        (Opcode::Set, "set"),
+        (Opcode::Jump, "jmp"),
+        (Opcode::JumpIfNotZero, "jnz"),
+        (Opcode::JumpIfZero, "jz"),
+    ];
+}
+
+/// The instructions a program may use and how many cycles each one takes, written one
+/// `mnemonic cycles` pair per line.
+#[derive(Clone, Debug, PartialEq)]
+struct InstructionTable {
+    cycles: BTreeMap<Opcode, u32>,
+}
+
+impl InstructionTable {
+    /// The instructions from the puzzle.
+    const STANDARD: &'static str = "noop 1
+add 2";
+
+    const EXTENDED: &'static str = "noop 1
+add 2
+sub 2
+mul 3
+set 1
+jmp 1
+jz 2
+jnz 2";
+}
+
+impl FromStr for InstructionTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cycles = BTreeMap::new();
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let (mnemonic, cost) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("expected a mnemonic and cycles in {line:?}"))?;
+            let (opcode, _) = Opcode::ALL
+                .iter()
+                .find(|(_, name)| *name == mnemonic)
+                .ok_or_else(|| format!("unknown instruction {mnemonic:?}"))?;
+            let cost = cost
+                .trim()
+                .parse()
+                .ok()
+                .filter(|cost| *cost > 0)
+                .ok_or_else(|| format!("{mnemonic} should take at least one cycle"))?;
+
+            cycles.insert(*opcode, cost);
+        }
+
+        Ok(InstructionTable { cycles })
+    }
+}
+
+/// A register, named by a lowercase letter.
+type Register = char;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    Value(i32),
+    Register(Register),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operation {
+    Noop,
+    Add(Register, Operand),
+    Sub(Register, Operand),
+    Mul(Register, Operand),
+    Set(Register, Operand),
+    /// Jumps are relative to the jump itself.
+    Jump(Operand),
+    JumpIfZero(Register, Operand),
+    JumpIfNotZero(Register, Operand),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Instruction {
+    operation: Operation,
+    cycles: u32,
+}
+
+/// Registers `a` through `z`, with `x` starting at 1 and the rest at 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Registers([i32; 26]);
+
+impl Default for Registers {
+    fn default() -> Self {
+        let mut registers = Registers([0; 26]);
+
+        *registers.get_mut('x') = 1;
 
-impl Instruction {
-    fn cycles(&self) -> u32 {
-        match self {
-            Noop => 1,
-            Add(_) => 2,
+        registers
+    }
+}
+
+impl Registers {
+    fn get(&self, register: Register) -> i32 {
+        self.0[(register as u8 - b'a') as usize]
+    }
+
+    fn get_mut(&mut self, register: Register) -> &mut i32 {
+        &mut self.0[(register as u8 - b'a') as usize]
+    }
+
+    fn value(&self, operand: Operand) -> i32 {
+        match operand {
+            Operand::Value(value) => value,
+            Operand::Register(register) => self.get(register),
         }
     }
 }
@@ -31,89 +149,416 @@ impl Instruction {
 fn main() {
     let input = read_file_input("10.txt".to_string());
 
+    if env::args().any(|arg| arg == "--trace") {
+        let table = match arg_value("--table").as_deref() {
+            Some("extended") => InstructionTable::EXTENDED.to_string(),
+            Some(path) => fs::read_to_string(path).expect("Table should be readable"),
+            None => InstructionTable::STANDARD.to_string(),
+        };
+        let table = table
+            .parse()
+            .unwrap_or_else(|error| panic!("Invalid table: {error}"));
+        let registers = match registers(&arg_value("--registers").unwrap_or("x".to_string())) {
+            Ok(registers) => registers,
+            Err(error) => {
+                eprintln!("  Invalid registers: {error}");
+                return;
+            }
+        };
+        let max_cycles = arg_value("--max-cycles").map_or(10_000, |cycles| {
+            cycles.parse().expect("Max cycles should be a number")
+        });
+
+        let (_, instructions) = instructions(&table, &input).unwrap();
+        let trace = Cpu::new(&instructions).take(max_cycles);
+
+        println!("{}", render_trace(trace, &registers));
+    }
+
     println!("  Part 1: {}", part1(&input));
-    println!("  Part 2: \n\n{}", part2(&input));
+    if env::args().any(|arg| arg == "--crt") {
+        let number = |name: &str, default: usize| {
+            arg_value(name).map_or(default, |n| n.parse().expect("Expected a number"))
+        };
+        let character = |name: &str, default: char| {
+            arg_value(name).map_or(default, |c| c.chars().next().expect("Expected a character"))
+        };
+        let crt = Crt {
+            width: number("--width", Crt::STANDARD.width),
+            height: number("--height", Crt::STANDARD.height),
+            sprite_width: number("--sprite-width", Crt::STANDARD.sprite_width),
+        };
+
+        let table = InstructionTable::STANDARD.parse().unwrap();
+        let (_, instructions) = instructions(&table, &input).unwrap();
+        let framebuffer = crt.framebuffer(Cpu::new(&instructions));
+
+        println!(
+            "{}\n",
+            render_framebuffer(
+                &framebuffer,
+                character("--on", '#'),
+                character("--off", '.')
+            )
+        );
+    }
+
+    let screen = part2(&input);
+    let reading = read_crt(&screen);
+
+    println!("  Part 2: {}\n\n{screen}", reading.text);
+
+    for (position, glyph) in reading.unknown {
+        eprintln!("  Unknown glyph at {position}:\n{}", glyph.join("\n"));
+    }
 }
 
-fn instructions(input: &str) -> IResult<&str, Vec<Instruction>> {
-    let (input, instructions) = separated_list1(
-        newline,
-        alt((
-            tag("noop").map(|_| Noop),
-            preceded(tag("addx "), complete::i32).map(Add),
-        )),
-    )(input)?;
+/// A comma separated list of registers, like `x,y`.
+fn registers(list: &str) -> Result<Vec<Register>, String> {
+    list.split(',')
+        .map(|name| {
+            all_consuming(register)
+                .parse(name.trim())
+                .map(|(_, register)| register)
+                .map_err(|_| format!("{name:?} is not a register, expected a lowercase letter"))
+        })
+        .collect()
+}
 
-    Ok((input, instructions))
+fn register(input: &str) -> IResult<&str, Register> {
+    let (input, register) = satisfy(|c| c.is_ascii_lowercase()).parse(input)?;
+
+    Ok((input, register))
 }
 
-pub fn part1(input: &str) -> usize {
-    let notable_cycles = [20, 60, 100, 140, 180, 220];
-    let mut scores: BTreeMap<u32, i32> = BTreeMap::new();
+fn operand(input: &str) -> IResult<&str, Operand> {
+    let (input, operand) = alt((
+        complete::i32.map(Operand::Value),
+        register.map(Operand::Register),
+    ))
+    .parse(input)?;
 
-    let (_, instructions) = instructions(input).unwrap();
-    let mut x: i32 = 1;
-    let mut cycles: u32 = 0;
+    Ok((input, operand))
+}
 
-    for instruction in instructions.iter() {
-        if notable_cycles.contains(&(cycles + 1)) {
-            scores.insert(cycles + 1, (cycles as i32 + 1) * x);
-        }
+fn opcode(input: &str) -> IResult<&str, Opcode> {
+    Opcode::ALL
+        .iter()
+        .find(|(_, mnemonic)| input.starts_with(mnemonic))
+        .map(|(opcode, mnemonic)| (&input[mnemonic.len()..], *opcode))
+        .ok_or(Err::Error(error::Error::new(input, error::ErrorKind::Tag)))
+}
 
-        if notable_cycles.contains(&(cycles + 2)) {
-            scores.insert(cycles + 2, (cycles as i32 + 2) * x);
-        }
+/// Parses one instruction, as its mnemonic with the register it works on appended (`addx 3`),
+/// accepting only the instructions in `table`.
+fn instruction(table: &InstructionTable) -> impl FnMut(&str) -> IResult<&str, Instruction> + '_ {
+    move |input| {
+        let (input, opcode) =
+            verify(opcode, |opcode| table.cycles.contains_key(opcode)).parse(input)?;
+
+        let (input, operation) = match opcode {
+            Opcode::Noop => success(Operation::Noop).parse(input)?,
+            Opcode::Jump => preceded(char(' '), operand.map(Operation::Jump)).parse(input)?,
+            _ => {
+                let (input, register) = register(input)?;
+                let (input, operand) = preceded(char(' '), operand).parse(input)?;
 
-        cycles += instruction.cycles();
+                let operation = match opcode {
+                    Opcode::Add => Operation::Add(register, operand),
+                    Opcode::Sub => Operation::Sub(register, operand),
+                    Opcode::Mul => Operation::Mul(register, operand),
+                    Opcode::Set => Operation::Set(register, operand),
+                    Opcode::JumpIfZero => Operation::JumpIfZero(register, operand),
+                    Opcode::JumpIfNotZero => Operation::JumpIfNotZero(register, operand),
+                    Opcode::Noop | Opcode::Jump => unreachable!(),
+                };
 
-        match instruction {
-            Noop => {}
-            Add(num) => {
-                x += num;
+                (input, operation)
             }
         };
+
+        Ok((
+            input,
+            Instruction {
+                operation,
+                cycles: table.cycles[&opcode],
+            },
+        ))
     }
+}
+
+fn instructions<'a>(
+    table: &InstructionTable,
+    input: &'a str,
+) -> IResult<&'a str, Vec<Instruction>> {
+    let (input, instructions) = all_consuming(terminated(
+        separated_list1(newline, instruction(table)),
+        multispace0,
+    ))
+    .parse(input)?;
 
-    scores.values().sum::<i32>() as usize
+    Ok((input, instructions))
 }
 
-pub fn part2(input: &str) -> String {
-    let (_, instructions) = instructions(input).unwrap();
+/// The registers during one cycle, before any instruction finishing in that cycle takes
+/// effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CycleState {
+    cycle: u32,
+    registers: Registers,
+}
+
+impl CycleState {
+    fn x(&self) -> i32 {
+        self.registers.get('x')
+    }
+}
+
+/// Runs instructions one cycle at a time, yielding the state during each cycle. Registers
+/// wrap around on overflow. The program halts when it runs or jumps past either end, or when
+/// the cycle count would overflow.
+struct Cpu<'a> {
+    program: &'a [Instruction],
+    instruction_pointer: usize,
+    // Cycles left on the current instruction, or 0 before it has started.
+    cycles_left: u32,
+    cycle: u32,
+    registers: Registers,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: &'a [Instruction]) -> Cpu<'a> {
+        Cpu {
+            program,
+            instruction_pointer: 0,
+            cycles_left: 0,
+            cycle: 0,
+            registers: Registers::default(),
+        }
+    }
+
+    fn execute(&mut self, operation: Operation) {
+        let registers = &mut self.registers;
+        let mut jump = 1;
 
-    let mut x: i32 = 1;
-    let mut cycles: u32 = 0;
-    let mut crt_pixels: String = "".to_string();
+        match operation {
+            Operation::Noop => {}
+            Operation::Add(register, operand) => {
+                let value = registers.value(operand);
+                let target = registers.get_mut(register);
 
-    for instruction in instructions.iter() {
-        for cycle_add in 0..instruction.cycles() {
-            let pixel_id = (cycles as i32 + cycle_add as i32) % 40;
+                *target = target.wrapping_add(value);
+            }
+            Operation::Sub(register, operand) => {
+                let value = registers.value(operand);
+                let target = registers.get_mut(register);
 
-            if ((x - 1)..=(x + 1)).contains(&pixel_id) {
-                crt_pixels.push('#');
-                continue;
+                *target = target.wrapping_sub(value);
             }
+            Operation::Mul(register, operand) => {
+                let value = registers.value(operand);
+                let target = registers.get_mut(register);
 
-            crt_pixels.push('.');
+                *target = target.wrapping_mul(value);
+            }
+            Operation::Set(register, operand) => {
+                *registers.get_mut(register) = registers.value(operand)
+            }
+            Operation::Jump(offset) => jump = registers.value(offset),
+            Operation::JumpIfZero(register, offset) => {
+                if registers.get(register) == 0 {
+                    jump = registers.value(offset);
+                }
+            }
+            Operation::JumpIfNotZero(register, offset) => {
+                if registers.get(register) != 0 {
+                    jump = registers.value(offset);
+                }
+            }
         }
 
-        cycles += instruction.cycles();
+        // Jumping before the start wraps to a huge pointer, which halts just the same.
+        self.instruction_pointer = self.instruction_pointer.wrapping_add_signed(jump as isize);
+    }
+}
 
-        match instruction {
-            Noop => {}
-            Add(num) => {
-                x += num;
-            }
+impl Iterator for Cpu<'_> {
+    type Item = CycleState;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = *self.program.get(self.instruction_pointer)?;
+
+        if self.cycles_left == 0 {
+            self.cycles_left = instruction.cycles;
+        }
+
+        self.cycle = self.cycle.checked_add(1)?;
+        self.cycles_left -= 1;
+
+        let state = CycleState {
+            cycle: self.cycle,
+            registers: self.registers,
         };
+
+        if self.cycles_left == 0 {
+            self.execute(instruction.operation);
+        }
+
+        Some(state)
+    }
+}
+
+fn signal_strength(trace: impl Iterator<Item = CycleState>) -> i32 {
+    let notable_cycles = [20, 60, 100, 140, 180, 220];
+
+    trace
+        .filter(|state| notable_cycles.contains(&state.cycle))
+        .map(|state| state.cycle as i32 * state.x())
+        .sum()
+}
+
+/// A display drawn one pixel per cycle, left to right and top to bottom. Each pixel is lit
+/// when the sprite, `sprite_width` pixels wide and centred on `x`, covers its column. Even
+/// widths leave the extra pixel to the right of `x`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Crt {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+}
+
+impl Crt {
+    const STANDARD: Crt = Crt {
+        width: 40,
+        height: 6,
+        sprite_width: 3,
+    };
+
+    /// The lit pixels, row by row. Cycles past the last pixel are ignored and pixels the
+    /// trace doesn't reach stay dark.
+    fn framebuffer(&self, trace: impl Iterator<Item = CycleState>) -> Vec<Vec<bool>> {
+        let mut framebuffer = vec![vec![false; self.width]; self.height];
+
+        for (pixel, state) in trace.take(self.width * self.height).enumerate() {
+            let (row, col) = (pixel / self.width, pixel % self.width);
+            let left = state.x() as i64 - (self.sprite_width as i64 - 1) / 2;
+
+            framebuffer[row][col] = (left..left + self.sprite_width as i64).contains(&(col as i64));
+        }
+
+        framebuffer
     }
+}
 
-    crt_pixels
-        .chars()
-        .chunks(40)
-        .into_iter()
-        .map(|chunk| chunk.collect::<String>())
+fn render_framebuffer(framebuffer: &[Vec<bool>], on: char, off: char) -> String {
+    framebuffer
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&lit| if lit { on } else { off })
+                .collect::<String>()
+        })
         .join("\n")
 }
 
+/// The standard Advent of Code font, as the rows of each letter's 4-pixel-wide glyph.
+const FONT: [(char, [&str; 6]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Text read off the CRT, with `?` for glyphs not in the font.
+#[derive(Debug, PartialEq)]
+struct Reading {
+    text: String,
+    /// The positions of the glyphs that couldn't be read, with their pixel rows.
+    unknown: Vec<(usize, Vec<String>)>,
+}
+
+/// Reads letters off a rendered CRT, taking each glyph as 4 pixels followed by a 1-pixel gap.
+fn read_crt(screen: &str) -> Reading {
+    let rows = screen
+        .lines()
+        .map(|row| row.chars().collect::<Vec<char>>())
+        .collect::<Vec<Vec<char>>>();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut reading = Reading {
+        text: String::new(),
+        unknown: vec![],
+    };
+
+    for (position, left) in (0..width).step_by(5).enumerate() {
+        let glyph = rows
+            .iter()
+            .map(|row| {
+                (left..left + 4)
+                    .map(|col| row.get(col).copied().unwrap_or('.'))
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+
+        match FONT.iter().find(|(_, pixels)| glyph == pixels) {
+            Some((letter, _)) => reading.text.push(*letter),
+            None => {
+                reading.text.push('?');
+                reading.unknown.push((position, glyph));
+            }
+        }
+    }
+
+    reading
+}
+
+/// The trace as CSV, one row per cycle with a column for each of `registers`.
+fn render_trace(trace: impl Iterator<Item = CycleState>, registers: &[Register]) -> String {
+    let header = iter::once("cycle".to_string())
+        .chain(registers.iter().map(char::to_string))
+        .join(",");
+    let rows = trace.map(|state| {
+        iter::once(state.cycle as i32)
+            .chain(
+                registers
+                    .iter()
+                    .map(|register| state.registers.get(*register)),
+            )
+            .join(",")
+    });
+
+    iter::once(header).chain(rows).join("\n")
+}
+
+pub fn part1(input: &str) -> usize {
+    let table = InstructionTable::STANDARD.parse().unwrap();
+    let (_, instructions) = instructions(&table, input).unwrap();
+
+    signal_strength(Cpu::new(&instructions)) as usize
+}
+
+pub fn part2(input: &str) -> String {
+    let table = InstructionTable::STANDARD.parse().unwrap();
+    let (_, instructions) = instructions(&table, input).unwrap();
+
+    let framebuffer = Crt::STANDARD.framebuffer(Cpu::new(&instructions));
+
+    render_framebuffer(&framebuffer, '#', '.')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,4 +727,198 @@ noop";
 #######.......#######.......#######....."
         );
     }
+
+    #[test]
+    fn cpu_works() {
+        let table = InstructionTable::STANDARD.parse().unwrap();
+        let (_, instructions) = instructions(&table, "noop\naddx 3\naddx -5").unwrap();
+        let trace = Cpu::new(&instructions).collect::<Vec<CycleState>>();
+
+        assert_eq!(
+            trace.iter().map(CycleState::x).collect::<Vec<i32>>(),
+            vec![1, 1, 1, 4, 4]
+        );
+        assert_eq!(
+            render_trace(trace.into_iter(), &['x']),
+            "cycle,x
+1,1
+2,1
+3,1
+4,4
+5,4"
+        );
+    }
+
+    #[test]
+    fn extended_instructions_work() {
+        let table = InstructionTable::EXTENDED.parse().unwrap();
+        let program = "setx 3
+sety 0
+addy x
+subx 1
+jnzx -2
+muly 2
+noop";
+        let (rest, instructions) = instructions(&table, program).unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            instructions[4],
+            Instruction {
+                operation: Operation::JumpIfNotZero('x', Operand::Value(-2)),
+                cycles: 2
+            }
+        );
+
+        let mut cpu = Cpu::new(&instructions);
+        let trace = cpu.by_ref().collect::<Vec<CycleState>>();
+
+        assert_eq!(trace.len(), 1 + 1 + 3 * (2 + 2 + 2) + 3 + 1);
+        assert_eq!(cpu.registers.get('x'), 0);
+        assert_eq!(cpu.registers.get('y'), 12);
+        assert_eq!(
+            render_trace(trace.into_iter().take(4), &['x', 'y']),
+            "cycle,x,y
+1,1,0
+2,3,0
+3,3,0
+4,3,0"
+        );
+    }
+
+    #[test]
+    fn registers_works() {
+        assert_eq!(registers("x"), Ok(vec!['x']));
+        assert_eq!(registers("x, y"), Ok(vec!['x', 'y']));
+        assert!(registers("X").is_err());
+        assert!(registers(" ").is_err());
+        assert!(registers("xy").is_err());
+        assert!(registers("x,").is_err());
+    }
+
+    #[test]
+    fn instruction_table_works() {
+        let standard = InstructionTable::STANDARD.parse().unwrap();
+        let table = "noop 4\nmul 1".parse::<InstructionTable>().unwrap();
+
+        assert!(instructions(&standard, "mulx 2").is_err());
+        assert!(instructions(&standard, "addx 3\nmulx 2\naddx 5").is_err());
+        assert!(instructions(&standard, "addx 3\ngarbage\naddx 5").is_err());
+        assert_eq!(
+            instructions(&standard, "addx 3\naddx 5\n").unwrap().1.len(),
+            2
+        );
+        assert_eq!(
+            instructions(&table, "mulx 2\nnoop").unwrap().1,
+            vec![
+                Instruction {
+                    operation: Operation::Mul('x', Operand::Value(2)),
+                    cycles: 1
+                },
+                Instruction {
+                    operation: Operation::Noop,
+                    cycles: 4
+                },
+            ]
+        );
+        assert_eq!(
+            "div 2".parse::<InstructionTable>(),
+            Err("unknown instruction \"div\"".to_string())
+        );
+        assert_eq!(
+            "add 0".parse::<InstructionTable>(),
+            Err("add should take at least one cycle".to_string())
+        );
+    }
+
+    #[test]
+    fn read_crt_works() {
+        let screen = "####.####.####..##..#..#...##..##..###..
+#.......#.#....#..#.#..#....#.#..#.#..#.
+###....#..###..#....####....#.#..#.###..
+#.....#...#....#....#..#....#.####.#..#.
+#....#....#....#..#.#..#.#..#.#..#.#..#.
+####.####.#.....##..#..#..##..#..#.###..";
+
+        assert_eq!(
+            read_crt(screen),
+            Reading {
+                text: "EZFCHJAB".to_string(),
+                unknown: vec![]
+            }
+        );
+
+        let reading = read_crt(&part2(INPUT));
+
+        assert_eq!(reading.text, "????????");
+        assert_eq!(
+            reading.unknown[0],
+            (
+                0,
+                vec!["##..", "###.", "####", "####", "####", "####"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn crt_geometry_works() {
+        let table = InstructionTable::STANDARD.parse().unwrap();
+        let (_, instructions) = instructions(&table, INPUT).unwrap();
+        let crt = |width, height, sprite_width| Crt {
+            width,
+            height,
+            sprite_width,
+        };
+
+        let framebuffer = crt(8, 2, 1).framebuffer(Cpu::new(&instructions));
+
+        assert_eq!(
+            framebuffer[0],
+            vec![false, true, false, false, false, true, false, false]
+        );
+        assert_eq!(
+            render_framebuffer(&framebuffer, 'x', ' '),
+            " x   x  \n        "
+        );
+        assert_eq!(
+            render_framebuffer(
+                &crt(10, 3, 4).framebuffer(Cpu::new(&instructions)),
+                '#',
+                '.'
+            ),
+            "##..##..##
+....##....
+.........."
+        );
+
+        let framebuffer = crt(40, 7, 3).framebuffer(Cpu::new(&instructions));
+
+        assert_eq!(
+            render_framebuffer(&framebuffer[..6], '#', '.'),
+            part2(INPUT)
+        );
+        assert!(framebuffer[6].iter().all(|&lit| !lit));
+    }
+
+    #[test]
+    fn overflow_wraps_or_halts() {
+        let table = InstructionTable::EXTENDED.parse().unwrap();
+        let (_, program) = instructions(&table, "setx 100000\nmulx x\nsuby 1").unwrap();
+        let mut cpu = Cpu::new(&program);
+
+        cpu.by_ref().for_each(drop);
+
+        assert_eq!(cpu.registers.get('x'), 100_000_i32.wrapping_mul(100_000));
+        assert_eq!(cpu.registers.get('y'), -1);
+
+        let (_, program) = instructions(&table, "jmp 0").unwrap();
+        let mut cpu = Cpu::new(&program);
+
+        cpu.cycle = u32::MAX - 1;
+
+        assert_eq!(cpu.count(), 1);
+    }
 }
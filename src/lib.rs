@@ -1,4 +1,4 @@
-use std::{env, fs};
+use std::{env, fs, fs::File};
 
 pub fn read_file_input(file: String) -> String {
     let cwd = env::current_dir().unwrap();
@@ -6,3 +6,15 @@ pub fn read_file_input(file: String) -> String {
 
     fs::read_to_string(path).unwrap()
 }
+
+pub fn open_file_input(file: String) -> File {
+    let cwd = env::current_dir().unwrap();
+    let path = cwd.join("src/inputs").join(file);
+
+    File::open(path).unwrap()
+}
+
+/// The value following a `--flag` on the command line, if the flag was given.
+pub fn arg_value(name: &str) -> Option<String> {
+    env::args().skip_while(|arg| arg != name).nth(1)
+}